@@ -0,0 +1,121 @@
+//! Tuning without an executor, for firmware built around a superloop.
+
+use core::{
+    future::Future,
+    ops::{Add, Sub},
+    pin::pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::One;
+use uxt::Ux;
+
+use crate::{
+    cache::Cache,
+    config::TuneConfig,
+    error::{MeasureError, TuneError},
+    measurement::MeasurementStrategy,
+    period::{MicrosPeriod, Period},
+    progress::TuneObserver,
+    table::TuningTable,
+    tuning::TuningSystem,
+    OscfExt, TryOscf,
+};
+
+/// [`TryOscf`] with calls that return once the work is done.
+pub trait OscfBlocking {
+    type DacValue: Copy + 'static + Ux;
+    type Period: Period = MicrosPeriod;
+    type Error;
+
+    fn get_period(&mut self, timeout: Duration) -> Result<Self::Period, MeasureError<Self::Error>>;
+    fn set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error>;
+    fn set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error>;
+
+    /// Same as [`TryOscf::try_delay`], reading periods unless overridden.
+    fn delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        let mut waited = 0.0;
+        while waited < duration.as_secs_f64() {
+            waited += self.get_period(timeout)?.seconds() * Self::Period::CYCLES as f64;
+        }
+        Ok(())
+    }
+
+    /// Same as [`OscfExt::tune_midi_frequencies`], which does the work.
+    fn tune_midi_frequencies<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
+        &mut self,
+        main_table: &mut A,
+        offset_table: &mut A,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> Result<Self::DacValue, TuneError<Self::Error, Self::Period>>
+    where
+        <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+            + Sub<Output = <Self::DacValue as Ux>::Rep>
+            + One
+            + PartialOrd
+            + Average
+            + Copy,
+    {
+        run(Blocking(self).tune_midi_frequencies(main_table, offset_table, cache, config))
+    }
+}
+
+// None of the futures built on `Blocking` ever wait, so one poll finishes them.
+fn run<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("should never happen"),
+    }
+}
+
+struct Blocking<'a, O: ?Sized>(&'a mut O);
+
+impl<'a, O: OscfBlocking + ?Sized> TryOscf for Blocking<'a, O> {
+    type DacValue = O::DacValue;
+    type Period = O::Period;
+    type Error = O::Error;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        self.0.get_period(timeout)
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.0.set_main_dac(value)
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.0.set_offset_dac(value)
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        self.0.delay(duration, timeout)
+    }
+}
+
+impl<'a, O: OscfBlocking + ?Sized> OscfExt for Blocking<'a, O> where
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
@@ -1,71 +1,528 @@
-use core::{array::from_fn, cell::Cell, marker::PhantomData};
+use core::{array::from_fn, cell::Cell, marker::PhantomData, time::Duration};
 
-use crate::key_frequencies::MicrosPeriod;
+use crate::period::{MicrosPeriod, Period};
 use num_traits::AsPrimitive;
 use uxt::Ux;
 
 pub trait Cache {
     type Index;
-    fn get(&self, index: Self::Index) -> Option<MicrosPeriod>;
-    fn set(&self, index: Self::Index, value: MicrosPeriod);
+    type Period: Period;
+    fn get(&self, index: Self::Index) -> Option<Self::Period>;
+    fn set(&self, index: Self::Index, value: Self::Period);
+
+    /// Called before every search that goes through the cache.
+    fn begin_search(&self) {}
+}
+
+/// Lookup counts of a bounded cache, for sizing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    /// Entries dropped to make room for others.
+    pub evictions: usize,
+    /// Entries found but too old to trust, also counted as misses.
+    pub expired: usize,
+}
+
+impl CacheStats {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    pub fn hit_rate(&self) -> f32 {
+        match self.lookups() {
+            0 => 0.0,
+            lookups => self.hits as f32 / lookups as f32,
+        }
+    }
+}
+
+/// Decides when a cached period no longer describes the oscillator. Stale
+/// entries read as missing, so searches measure them again.
+pub trait Expiry {
+    type Stamp: Copy;
+
+    /// Stamp stored along a period measured now.
+    fn stamp(&self) -> Self::Stamp;
+    fn is_fresh(&self, stamp: Self::Stamp) -> bool;
+}
+
+/// Entries never go stale.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoExpiry;
+
+impl Expiry for NoExpiry {
+    type Stamp = ();
+
+    fn stamp(&self) {}
+
+    fn is_fresh(&self, _stamp: ()) -> bool {
+        true
+    }
+}
+
+/// Entries go stale on [`Generation::invalidate`], e.g. when the temperature
+/// changed.
+#[derive(Debug, Default)]
+pub struct Generation(Cell<u32>);
+
+impl Generation {
+    pub const fn new() -> Self {
+        Self(Cell::new(0))
+    }
+
+    /// Marks every entry stored so far as stale.
+    pub fn invalidate(&self) {
+        self.0.set(self.0.get().wrapping_add(1));
+    }
+}
+
+impl Expiry for Generation {
+    type Stamp = u32;
+
+    fn stamp(&self) -> u32 {
+        self.0.get()
+    }
+
+    fn is_fresh(&self, stamp: u32) -> bool {
+        stamp == self.0.get()
+    }
+}
+
+/// Monotonic time since an arbitrary epoch.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// Entries go stale `max_age` after they were measured, or on
+/// [`MaxAge::invalidate`].
+#[derive(Debug)]
+pub struct MaxAge<K> {
+    clock: K,
+    max_age: Duration,
+    generation: Generation,
+}
+
+impl<K: Clock> MaxAge<K> {
+    pub fn new(clock: K, max_age: Duration) -> Self {
+        Self {
+            clock,
+            max_age,
+            generation: Generation::new(),
+        }
+    }
+
+    /// Marks every entry stored so far as stale.
+    pub fn invalidate(&self) {
+        self.generation.invalidate();
+    }
 }
 
-pub struct NoCache<Index>(PhantomData<Index>);
+impl<K: Clock> Expiry for MaxAge<K> {
+    type Stamp = (u32, Duration);
 
-impl<Index> Default for NoCache<Index> {
+    fn stamp(&self) -> Self::Stamp {
+        (self.generation.stamp(), self.clock.now())
+    }
+
+    fn is_fresh(&self, (generation, measured): Self::Stamp) -> bool {
+        self.generation.is_fresh(generation)
+            && self.clock.now().saturating_sub(measured) <= self.max_age
+    }
+}
+
+#[derive(Default)]
+struct Counters(Cell<CacheStats>);
+
+impl Counters {
+    fn update(&self, f: impl FnOnce(&mut CacheStats)) {
+        let mut stats = self.0.get();
+        f(&mut stats);
+        self.0.set(stats);
+    }
+
+    fn lookup<P, E: Expiry>(&self, expiry: &E, found: Option<(P, E::Stamp)>) -> Option<P> {
+        let fresh = found.as_ref().map(|(_, stamp)| expiry.is_fresh(*stamp));
+        self.update(|stats| match fresh {
+            Some(true) => stats.hits += 1,
+            Some(false) => {
+                stats.misses += 1;
+                stats.expired += 1;
+            }
+            None => stats.misses += 1,
+        });
+        found
+            .filter(|_| fresh == Some(true))
+            .map(|(period, _)| period)
+    }
+
+    fn evict(&self) {
+        self.update(|stats| stats.evictions += 1);
+    }
+}
+
+pub struct NoCache<Index, P = MicrosPeriod>(PhantomData<(Index, P)>);
+
+impl<Index, P> Default for NoCache<Index, P> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<Index> NoCache<Index> {
+impl<Index, P> NoCache<Index, P> {
     pub fn new() -> Self {
         Self(PhantomData)
     }
 }
 
-impl<Index> Cache for NoCache<Index> {
+impl<Index, P: Period> Cache for NoCache<Index, P> {
     type Index = Index;
+    type Period = P;
 
-    fn get(&self, _index: Self::Index) -> Option<MicrosPeriod> {
+    fn get(&self, _index: Self::Index) -> Option<P> {
         None
     }
 
-    fn set(&self, _index: Self::Index, _value: MicrosPeriod) {}
+    fn set(&self, _index: Self::Index, _value: P) {}
 }
 
-pub struct FixedCache<Index, const SIZE: usize> {
-    periods: [Cell<Option<MicrosPeriod>>; SIZE],
+type Slot<P, S> = Cell<Option<(P, S)>>;
+
+pub struct FixedCache<Index, const SIZE: usize, P = MicrosPeriod, E: Expiry = NoExpiry> {
+    periods: [Slot<P, E::Stamp>; SIZE],
+    expiry: E,
     phantom: PhantomData<Index>,
 }
 
-impl<Index, const SIZE: usize> FixedCache<Index, SIZE> {
+impl<Index, const SIZE: usize, P, E: Expiry + Default> FixedCache<Index, SIZE, P, E> {
     pub fn new() -> Self {
+        Self::with_expiry(E::default())
+    }
+}
+
+impl<Index, const SIZE: usize, P, E: Expiry> FixedCache<Index, SIZE, P, E> {
+    pub fn with_expiry(expiry: E) -> Self {
         Self {
             periods: from_fn(|_| Cell::new(None)),
+            expiry,
             phantom: PhantomData,
         }
     }
+
+    pub fn expiry(&self) -> &E {
+        &self.expiry
+    }
+}
+
+impl<Index, const SIZE: usize, P, E: Expiry + Default> Default for FixedCache<Index, SIZE, P, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, Index, P: Period, E: Expiry> Cache for FixedCache<Index, SIZE, P, E>
+where
+    Index: Ux, //SCRATCH
+    Index::Rep: Copy + 'static + AsPrimitive<usize>,
+{
+    type Index = Index;
+    type Period = P;
+
+    fn get(&self, index: Self::Index) -> Option<P> {
+        let (period, stamp) = self.periods[index.into().as_()].get()?;
+        self.expiry.is_fresh(stamp).then_some(period)
+    }
+
+    fn set(&self, index: Self::Index, value: P) {
+        self.periods[index.into().as_()].set(Some((value, self.expiry.stamp())))
+    }
+}
+
+type Entry<Index, P, S> = Cell<Option<(Index, P, S)>>;
+
+// An entry with the time of its last use.
+type LruEntry<Index, P, S> = Cell<Option<(Index, P, S, usize)>>;
+
+/// Keeps the `CAPACITY` most recently used codes.
+pub struct LruCache<Index, const CAPACITY: usize, P = MicrosPeriod, E: Expiry = NoExpiry> {
+    entries: [LruEntry<Index, P, E::Stamp>; CAPACITY],
+    clock: Cell<usize>,
+    expiry: E,
+    counters: Counters,
+}
+
+impl<Index, const CAPACITY: usize, P, E: Expiry + Default> LruCache<Index, CAPACITY, P, E> {
+    pub fn new() -> Self {
+        Self::with_expiry(E::default())
+    }
+}
+
+impl<Index, const CAPACITY: usize, P, E: Expiry> LruCache<Index, CAPACITY, P, E> {
+    pub fn with_expiry(expiry: E) -> Self {
+        Self {
+            entries: from_fn(|_| Cell::new(None)),
+            clock: Cell::new(0),
+            expiry,
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn expiry(&self) -> &E {
+        &self.expiry
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.counters.0.get()
+    }
+
+    fn tick(&self) -> usize {
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+        now
+    }
+}
+
+impl<Index, const CAPACITY: usize, P, E: Expiry + Default> Default
+    for LruCache<Index, CAPACITY, P, E>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Index: Copy + PartialEq, const CAPACITY: usize, P: Period, E: Expiry> Cache
+    for LruCache<Index, CAPACITY, P, E>
+{
+    type Index = Index;
+    type Period = P;
+
+    fn get(&self, index: Self::Index) -> Option<P> {
+        let found = self.entries.iter().find_map(|entry| match entry.get() {
+            Some((i, period, stamp, _)) if i == index => {
+                entry.set(Some((i, period, stamp, self.tick())));
+                Some((period, stamp))
+            }
+            _ => None,
+        });
+        self.counters.lookup(&self.expiry, found)
+    }
+
+    fn set(&self, index: Self::Index, value: P) {
+        let now = self.tick();
+        // The entry for `index` if present, else a free one, else the least
+        // recently used.
+        let slot = self.entries.iter().min_by_key(|entry| match entry.get() {
+            Some((i, _, _, _)) if i == index => (0, 0),
+            None => (1, 0),
+            Some((_, _, _, used)) => (2, used),
+        });
+        let Some(slot) = slot else {
+            return;
+        };
+        if matches!(slot.get(), Some((i, _, _, _)) if i != index) {
+            self.counters.evict();
+        }
+        slot.set(Some((index, value, self.expiry.stamp(), now)));
+    }
+}
+
+/// Keeps each code in one of `SLOTS` slots picked by a hash of the code, so
+/// lookups take constant time but colliding codes evict each other.
+pub struct DirectMappedCache<Index, const SLOTS: usize, P = MicrosPeriod, E: Expiry = NoExpiry> {
+    slots: [Entry<Index, P, E::Stamp>; SLOTS],
+    expiry: E,
+    counters: Counters,
+}
+
+impl<Index, const SLOTS: usize, P, E: Expiry + Default> DirectMappedCache<Index, SLOTS, P, E> {
+    pub fn new() -> Self {
+        Self::with_expiry(E::default())
+    }
+}
+
+impl<Index, const SLOTS: usize, P, E: Expiry> DirectMappedCache<Index, SLOTS, P, E> {
+    pub fn with_expiry(expiry: E) -> Self {
+        const { assert!(SLOTS > 0) };
+        Self {
+            slots: from_fn(|_| Cell::new(None)),
+            expiry,
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn expiry(&self) -> &E {
+        &self.expiry
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.counters.0.get()
+    }
 }
 
-impl<Index, const SIZE: usize> Default for FixedCache<Index, SIZE> {
+impl<Index, const SLOTS: usize, P, E: Expiry + Default> Default
+    for DirectMappedCache<Index, SLOTS, P, E>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<const SIZE: usize, Index> Cache for FixedCache<Index, SIZE>
+impl<Index, const SLOTS: usize, P: Period, E: Expiry> DirectMappedCache<Index, SLOTS, P, E>
+where
+    Index: Ux,
+    Index::Rep: Copy + 'static + AsPrimitive<usize>,
+{
+    // Fibonacci hashing, so the codes of one search spread over the slots.
+    fn slot(&self, index: Index) -> &Entry<Index, P, E::Stamp> {
+        let hash = (index.into().as_() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        &self.slots[((hash >> 32) as usize) % SLOTS]
+    }
+}
+
+impl<Index, const SLOTS: usize, P: Period, E: Expiry> Cache
+    for DirectMappedCache<Index, SLOTS, P, E>
 where
-    Index: Ux<VALUE_COUNT = { SIZE }>,
+    Index: Ux + Copy + PartialEq,
     Index::Rep: Copy + 'static + AsPrimitive<usize>,
 {
     type Index = Index;
+    type Period = P;
+
+    fn get(&self, index: Self::Index) -> Option<P> {
+        let found = match self.slot(index).get() {
+            Some((i, period, stamp)) if i == index => Some((period, stamp)),
+            _ => None,
+        };
+        self.counters.lookup(&self.expiry, found)
+    }
+
+    fn set(&self, index: Self::Index, value: P) {
+        let slot = self.slot(index);
+        if matches!(slot.get(), Some((i, _, _)) if i != index) {
+            self.counters.evict();
+        }
+        slot.set(Some((index, value, self.expiry.stamp())));
+    }
+}
+
+/// Keeps the codes visited by the last `PATHS` searches, up to `DEPTH` each.
+/// Neighbouring anchors share the start of their search paths, which is
+/// where the hits come from. A binary search over a `B` bit DAC visits at
+/// most `B + 2` codes.
+pub struct SearchPathCache<
+    Index,
+    const DEPTH: usize,
+    const PATHS: usize,
+    P = MicrosPeriod,
+    E: Expiry = NoExpiry,
+> {
+    paths: [[Entry<Index, P, E::Stamp>; DEPTH]; PATHS],
+    path: Cell<usize>,
+    depth: Cell<usize>,
+    expiry: E,
+    counters: Counters,
+}
+
+impl<Index, const DEPTH: usize, const PATHS: usize, P, E: Expiry + Default>
+    SearchPathCache<Index, DEPTH, PATHS, P, E>
+{
+    pub fn new() -> Self {
+        Self::with_expiry(E::default())
+    }
+}
+
+impl<Index, const DEPTH: usize, const PATHS: usize, P, E: Expiry>
+    SearchPathCache<Index, DEPTH, PATHS, P, E>
+{
+    pub fn with_expiry(expiry: E) -> Self {
+        Self {
+            paths: from_fn(|_| from_fn(|_| Cell::new(None))),
+            path: Cell::new(0),
+            depth: Cell::new(0),
+            expiry,
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn expiry(&self) -> &E {
+        &self.expiry
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.counters.0.get()
+    }
+}
+
+impl<Index, const DEPTH: usize, const PATHS: usize, P, E: Expiry + Default> Default
+    for SearchPathCache<Index, DEPTH, PATHS, P, E>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Index: Copy, const DEPTH: usize, const PATHS: usize, P: Period, E: Expiry>
+    SearchPathCache<Index, DEPTH, PATHS, P, E>
+{
+    fn record(&self, index: Index, value: P, stamp: E::Stamp) {
+        let depth = self.depth.get();
+        if let Some(entry) = self
+            .paths
+            .get(self.path.get())
+            .and_then(|path| path.get(depth))
+        {
+            entry.set(Some((index, value, stamp)));
+            self.depth.set(depth + 1);
+        }
+    }
+}
+
+impl<Index: Copy + PartialEq, const DEPTH: usize, const PATHS: usize, P: Period, E: Expiry> Cache
+    for SearchPathCache<Index, DEPTH, PATHS, P, E>
+{
+    type Index = Index;
+    type Period = P;
 
-    fn get(&self, index: Self::Index) -> Option<MicrosPeriod> {
-        self.periods[index.into().as_()].get()
+    fn get(&self, index: Self::Index) -> Option<P> {
+        let current = self.path.get();
+        let found = self.paths.iter().enumerate().find_map(|(path, entries)| {
+            entries.iter().find_map(|entry| match entry.get() {
+                Some((i, period, stamp)) if i == index => Some((path, period, stamp)),
+                _ => None,
+            })
+        });
+        let period = self.counters.lookup(
+            &self.expiry,
+            found.map(|(_, period, stamp)| (period, stamp)),
+        )?;
+        // Codes shared with an earlier path belong to the current one too.
+        if let Some((path, period, stamp)) = found {
+            if path != current {
+                self.record(index, period, stamp);
+            }
+        }
+        Some(period)
     }
 
-    fn set(&self, index: Self::Index, value: MicrosPeriod) {
-        self.periods[index.into().as_()].set(Some(value))
+    fn set(&self, index: Self::Index, value: P) {
+        self.record(index, value, self.expiry.stamp());
+    }
+
+    fn begin_search(&self) {
+        let path = (self.path.get() + 1) % PATHS.max(1);
+        for entry in self.paths.get(path).into_iter().flatten() {
+            if entry.take().is_some() {
+                self.counters.evict();
+            }
+        }
+        self.path.set(path);
+        self.depth.set(0);
     }
 }
@@ -0,0 +1,427 @@
+use core::{
+    array::from_fn,
+    ops::{Add, Sub},
+};
+
+use num_integer::Average;
+use num_traits::{float::FloatCore, AsPrimitive, One};
+use uxt::Ux;
+
+use crate::{
+    cache::Cache,
+    config::TuneConfig,
+    domain::MidiOscDomain,
+    error::TemperatureTuneError,
+    measurement::MeasurementStrategy,
+    pitch::{split, PitchEngine},
+    progress::TuneObserver,
+    table::{lerp, Table},
+    tuning::{TuningSystem, A4},
+    OscfExt, TemperatureSensor,
+};
+
+#[cfg(feature = "postcard")]
+use crate::table::TABLE_LEN;
+
+/// Everything a device needs to play in tune after a power cycle.
+pub struct Calibration<T: Ux> {
+    pub main: Table<T>,
+    pub offset: Table<T>,
+    /// Offset DAC steps per main DAC step, as returned by `tune_midi_frequencies`.
+    pub ratio: T,
+    /// A4 frequency in Hz the tables were tuned against.
+    pub reference_pitch: f32,
+    /// Timestamp or tuning counter, opaque to this crate.
+    pub tuned_at: u32,
+    /// Temperature in °C the tables were measured at, when known.
+    pub temperature: Option<f32>,
+}
+
+impl<T: Ux + Copy> Calibration<T> {
+    pub fn new(main: Table<T>, offset: Table<T>, ratio: T) -> Self {
+        Self {
+            main,
+            offset,
+            ratio,
+            reference_pitch: 440.0,
+            tuned_at: 0,
+            temperature: None,
+        }
+    }
+
+    pub fn pitch_engine(&self) -> PitchEngine<'_, T>
+    where
+        T::Rep: AsPrimitive<f32>,
+        f32: AsPrimitive<T::Rep>,
+    {
+        PitchEngine::new(&self.main, &self.offset, self.ratio)
+    }
+}
+
+/// Calibrations of one oscillator in `BINS` temperature bins, `step` °C
+/// apart with the first centred on `coldest`.
+pub struct CalibrationSet<T: Ux, const BINS: usize> {
+    pub coldest: f32,
+    pub step: f32,
+    bins: [Option<Calibration<T>>; BINS],
+}
+
+impl<T: Ux + Copy, const BINS: usize> CalibrationSet<T, BINS> {
+    pub fn new(coldest: f32, step: f32) -> Self {
+        Self {
+            coldest,
+            step,
+            bins: from_fn(|_| None),
+        }
+    }
+
+    /// Bin whose centre is nearest to `temperature`.
+    pub fn bin(&self, temperature: f32) -> usize {
+        let bin = FloatCore::round((temperature - self.coldest) / self.step);
+        bin.clamp(0.0, BINS.saturating_sub(1) as f32) as usize
+    }
+
+    pub fn get(&self, bin: usize) -> Option<&Calibration<T>> {
+        self.bins.get(bin)?.as_ref()
+    }
+
+    /// Stores `calibration` as measured at `temperature` and returns the one
+    /// it replaces in that bin.
+    pub fn insert(
+        &mut self,
+        temperature: f32,
+        mut calibration: Calibration<T>,
+    ) -> Option<Calibration<T>> {
+        calibration.temperature = Some(temperature);
+        let bin = self.bin(temperature);
+        self.bins[bin].replace(calibration)
+    }
+
+    pub fn remove(&mut self, bin: usize) -> Option<Calibration<T>> {
+        self.bins.get_mut(bin)?.take()
+    }
+
+    pub fn is_missing(&self, temperature: f32) -> bool {
+        self.get(self.bin(temperature)).is_none()
+    }
+
+    /// DAC pair for `note` at `temperature`, interpolated between the
+    /// calibrations measured nearest below and above it. Outside of those the
+    /// nearest calibration is used as is. `None` while the set is empty.
+    pub fn dac_pair(&self, note: MidiOscDomain, cents: f32, temperature: f32) -> Option<(T, T)>
+    where
+        T::Rep: AsPrimitive<f32>,
+        f32: AsPrimitive<T::Rep>,
+    {
+        let measured = self
+            .bins
+            .iter()
+            .enumerate()
+            .filter_map(|(bin, calibration)| {
+                let calibration = calibration.as_ref()?;
+                let at = calibration
+                    .temperature
+                    .unwrap_or(self.coldest + bin as f32 * self.step);
+                Some((at, calibration))
+            });
+        let (mut below, mut above) = (None, None);
+        for (at, calibration) in measured {
+            if at <= temperature && below.is_none_or(|(b, _)| at > b) {
+                below = Some((at, calibration));
+            }
+            if at >= temperature && above.is_none_or(|(a, _)| at < a) {
+                above = Some((at, calibration));
+            }
+        }
+
+        let positions =
+            |calibration: &Calibration<T>| calibration.pitch_engine().positions(note, cents);
+        let (main, offset, ratio) = match (below, above) {
+            (Some((low, cold)), Some((high, hot))) if high > low => {
+                let t = (temperature - low) / (high - low);
+                let (cold, hot) = (positions(cold), positions(hot));
+                (
+                    lerp(cold.0, hot.0, t),
+                    lerp(cold.1, hot.1, t),
+                    lerp(cold.2, hot.2, t),
+                )
+            }
+            (Some((_, nearest)), _) | (None, Some((_, nearest))) => positions(nearest),
+            (None, None) => return None,
+        };
+        Some(split(main, offset, ratio))
+    }
+
+    /// Tunes `o` into the bin of the current temperature unless that bin is
+    /// already filled, and returns the bin it filled. The temperature
+    /// recorded is the mean of the readings before and after tuning, even
+    /// when a drift during the tune moves it into a neighbouring bin.
+    pub async fn fill_missing<O, S>(
+        &mut self,
+        o: &mut O,
+        sensor: &mut S,
+        cache: &impl Cache<Index = T, Period = O::Period>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<T, O::Period>,
+        >,
+    ) -> Result<Option<usize>, TemperatureTuneError<O::Error, S::Error, O::Period>>
+    where
+        O: OscfExt<DacValue = T> + ?Sized,
+        S: TemperatureSensor + ?Sized,
+        T::Rep: Add<Output = T::Rep>
+            + Sub<Output = T::Rep>
+            + One
+            + PartialOrd
+            + Average
+            + Copy
+            + AsPrimitive<f32>,
+    {
+        let before = sensor
+            .read_temperature()
+            .await
+            .map_err(TemperatureTuneError::Sensor)?;
+        let bin = self.bin(before);
+        if self.bins[bin].is_some() {
+            return Ok(None);
+        }
+
+        let mut main = Table::new();
+        let mut offset = Table::new();
+        let ratio = o
+            .tune_midi_frequencies(&mut main, &mut offset, cache, config)
+            .await?;
+        let after = sensor
+            .read_temperature()
+            .await
+            .map_err(TemperatureTuneError::Sensor)?;
+
+        self.bins[bin] = Some(Calibration {
+            reference_pitch: config.tuning.frequency(A4),
+            temperature: Some((before + after) / 2.0),
+            ..Calibration::new(main, offset, ratio)
+        });
+        Ok(Some(bin))
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RawCalibration<M, R> {
+    main: M,
+    offset: M,
+    ratio: R,
+    reference_pitch: f32,
+    tuned_at: u32,
+    temperature: Option<f32>,
+}
+
+// Format version 1, from before calibrations recorded their temperature.
+#[cfg(feature = "postcard")]
+#[derive(serde::Deserialize)]
+struct RawCalibrationV1<M, R> {
+    main: M,
+    offset: M,
+    ratio: R,
+    reference_pitch: f32,
+    tuned_at: u32,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Ux + Copy> serde::Serialize for Calibration<T>
+where
+    T::Rep: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawCalibration {
+            main: &self.main,
+            offset: &self.offset,
+            ratio: self.ratio.into(),
+            reference_pitch: self.reference_pitch,
+            tuned_at: self.tuned_at,
+            temperature: self.temperature,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Ux + Copy> serde::Deserialize<'de> for Calibration<T>
+where
+    T::Rep: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawCalibration::<Table<T>, T::Rep>::deserialize(deserializer)?;
+        let ratio = T::try_from(raw.ratio)
+            .map_err(|_| <D::Error as serde::de::Error>::custom("DAC value out of range"))?;
+        Ok(Self {
+            main: raw.main,
+            offset: raw.offset,
+            ratio,
+            reference_pitch: raw.reference_pitch,
+            tuned_at: raw.tuned_at,
+            temperature: raw.temperature,
+        })
+    }
+}
+
+#[cfg(feature = "postcard")]
+pub const FORMAT_VERSION: u8 = 3;
+
+#[cfg(feature = "postcard")]
+const MAGIC: [u8; 4] = *b"OSCT";
+
+// Versions 1 and 2 had no payload length in their header.
+#[cfg(feature = "postcard")]
+const LEGACY_HEADER_SIZE: usize = MAGIC.len() + 2;
+
+#[cfg(feature = "postcard")]
+const HEADER_SIZE: usize = LEGACY_HEADER_SIZE + 2;
+
+#[cfg(feature = "postcard")]
+const CRC_SIZE: usize = 4;
+
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    BufferTooSmall,
+    BadMagic,
+    UnsupportedVersion(u8),
+    DacWidthMismatch { expected: u8, found: u8 },
+    CrcMismatch,
+    Encoding(postcard::Error),
+}
+
+#[cfg(feature = "postcard")]
+impl From<postcard::Error> for CalibrationError {
+    fn from(error: postcard::Error) -> Self {
+        Self::Encoding(error)
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl<T: Ux + Copy> Calibration<T>
+where
+    T::Rep: serde::Serialize + for<'de> serde::Deserialize<'de>,
+{
+    /// Upper bound of the blob written by [`Self::to_bytes`].
+    pub const MAX_SIZE: usize = {
+        let dac = if T::BITS <= 8 { 1 } else { T::BITS.div_ceil(7) };
+        HEADER_SIZE + (2 * TABLE_LEN + 1) * dac + 4 + 5 + 5 + CRC_SIZE
+    };
+
+    /// Writes `magic | version | dac bits | payload length | postcard payload |
+    /// crc32` into `buf` and returns the used prefix.
+    pub fn to_bytes<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], CalibrationError> {
+        const { assert!(Self::MAX_SIZE <= u16::MAX as usize) };
+        if buf.len() < HEADER_SIZE + CRC_SIZE {
+            return Err(CalibrationError::BufferTooSmall);
+        }
+
+        buf[..MAGIC.len()].copy_from_slice(&MAGIC);
+        buf[MAGIC.len()] = FORMAT_VERSION;
+        buf[MAGIC.len() + 1] = T::BITS as u8;
+
+        let payload_end = buf.len() - CRC_SIZE;
+        let payload_len = match postcard::to_slice(self, &mut buf[HEADER_SIZE..payload_end]) {
+            Ok(payload) => payload.len(),
+            Err(postcard::Error::SerializeBufferFull) => {
+                return Err(CalibrationError::BufferTooSmall)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        buf[LEGACY_HEADER_SIZE..HEADER_SIZE].copy_from_slice(&(payload_len as u16).to_le_bytes());
+
+        let crc_at = HEADER_SIZE + payload_len;
+        let crc = crc32(&buf[..crc_at]);
+        buf[crc_at..crc_at + CRC_SIZE].copy_from_slice(&crc.to_le_bytes());
+        Ok(&mut buf[..crc_at + CRC_SIZE])
+    }
+
+    /// Parses a blob written by [`Self::to_bytes`], checking its CRC before
+    /// decoding the payload. Blobs of versions 1 and 2 have no payload length,
+    /// so they are decoded first to find their CRC; version 1 calibrations
+    /// carry no temperature. Trailing bytes are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CalibrationError> {
+        if bytes.len() < LEGACY_HEADER_SIZE + CRC_SIZE {
+            return Err(CalibrationError::BufferTooSmall);
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(CalibrationError::BadMagic);
+        }
+        let version = bytes[MAGIC.len()];
+        if !(1..=FORMAT_VERSION).contains(&version) {
+            return Err(CalibrationError::UnsupportedVersion(version));
+        }
+        let bits = bytes[MAGIC.len() + 1];
+        if bits as usize != T::BITS {
+            return Err(CalibrationError::DacWidthMismatch {
+                expected: T::BITS as u8,
+                found: bits,
+            });
+        }
+
+        if version == FORMAT_VERSION {
+            if bytes.len() < HEADER_SIZE {
+                return Err(CalibrationError::BufferTooSmall);
+            }
+            let payload_len =
+                u16::from_le_bytes([bytes[LEGACY_HEADER_SIZE], bytes[LEGACY_HEADER_SIZE + 1]]);
+            let crc_at = HEADER_SIZE + payload_len as usize;
+            check_crc(bytes, crc_at)?;
+            return Ok(postcard::from_bytes(&bytes[HEADER_SIZE..crc_at])?);
+        }
+
+        let (calibration, rest) = if version == 1 {
+            let (raw, rest) = postcard::take_from_bytes::<RawCalibrationV1<Table<T>, T::Rep>>(
+                &bytes[LEGACY_HEADER_SIZE..],
+            )?;
+            let ratio = T::try_from(raw.ratio)
+                .map_err(|_| CalibrationError::Encoding(postcard::Error::DeserializeBadEncoding))?;
+            let calibration = Self {
+                main: raw.main,
+                offset: raw.offset,
+                ratio,
+                reference_pitch: raw.reference_pitch,
+                tuned_at: raw.tuned_at,
+                temperature: None,
+            };
+            (calibration, rest)
+        } else {
+            postcard::take_from_bytes(&bytes[LEGACY_HEADER_SIZE..])?
+        };
+        check_crc(bytes, bytes.len() - rest.len())?;
+
+        Ok(calibration)
+    }
+}
+
+// Checks the CRC stored at `crc_at` against everything before it.
+#[cfg(feature = "postcard")]
+fn check_crc(bytes: &[u8], crc_at: usize) -> Result<(), CalibrationError> {
+    if bytes.len() < crc_at + CRC_SIZE {
+        return Err(CalibrationError::BufferTooSmall);
+    }
+    if crc32(&bytes[..crc_at]).to_le_bytes() != bytes[crc_at..crc_at + CRC_SIZE] {
+        return Err(CalibrationError::CrcMismatch);
+    }
+    Ok(())
+}
+
+// CRC-32/ISO-HDLC, bitwise to stay table free.
+#[cfg(feature = "postcard")]
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,110 @@
+use core::time::Duration;
+
+use crate::{
+    measurement::Single,
+    period::Period,
+    progress::NoObserver,
+    search::SearchStrategy,
+    settling::Settling,
+    tuning::{Edo, TuningSystem},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuneConfig<M = Single, S = Edo, R = NoObserver> {
+    /// Upper bound for a single period measurement.
+    pub timeout: Duration,
+    /// Relative slack applied to bracket and monotonicity checks so that
+    /// measurement jitter is not reported as a broken oscillator.
+    pub tolerance: f32,
+    /// How raw readings are combined, see [`crate::measurement`].
+    pub measurement: M,
+    pub search: SearchStrategy,
+    /// Wait after DAC writes, see [`crate::settling`].
+    pub settling: Settling,
+    /// Frequencies the anchors are tuned to, see [`crate::tuning`].
+    pub tuning: S,
+    /// Progress and cancellation, see [`crate::progress`].
+    pub observer: R,
+}
+
+impl Default for TuneConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(250),
+            tolerance: 0.002,
+            measurement: Single,
+            search: SearchStrategy::Bisection,
+            settling: Settling::default(),
+            tuning: Edo::default(),
+            observer: NoObserver,
+        }
+    }
+}
+
+impl<M, S, R> TuneConfig<M, S, R> {
+    pub fn with_measurement<N>(self, measurement: N) -> TuneConfig<N, S, R> {
+        TuneConfig {
+            timeout: self.timeout,
+            tolerance: self.tolerance,
+            measurement,
+            search: self.search,
+            settling: self.settling,
+            tuning: self.tuning,
+            observer: self.observer,
+        }
+    }
+
+    pub fn with_tuning<T: TuningSystem>(self, tuning: T) -> TuneConfig<M, T, R> {
+        TuneConfig {
+            timeout: self.timeout,
+            tolerance: self.tolerance,
+            measurement: self.measurement,
+            search: self.search,
+            settling: self.settling,
+            tuning,
+            observer: self.observer,
+        }
+    }
+
+    pub fn with_search(self, search: SearchStrategy) -> Self {
+        Self { search, ..self }
+    }
+
+    pub fn with_settling(self, settling: Settling) -> Self {
+        Self { settling, ..self }
+    }
+
+    pub fn with_observer<Q>(self, observer: Q) -> TuneConfig<M, S, Q> {
+        TuneConfig {
+            timeout: self.timeout,
+            tolerance: self.tolerance,
+            measurement: self.measurement,
+            search: self.search,
+            settling: self.settling,
+            tuning: self.tuning,
+            observer,
+        }
+    }
+
+    // Borrows everything but the tuning, for tunes whose targets do not come
+    // from `tuning`.
+    pub(crate) fn tuned_to<T>(&self, tuning: T) -> TuneConfig<&M, T, &R> {
+        TuneConfig {
+            timeout: self.timeout,
+            tolerance: self.tolerance,
+            measurement: &self.measurement,
+            search: self.search,
+            settling: self.settling,
+            tuning,
+            observer: &self.observer,
+        }
+    }
+
+    pub(crate) fn at_least<P: Period>(&self, period: P, bound: P) -> bool {
+        period.ticks() as f32 >= bound.ticks() as f32 * (1.0 - self.tolerance)
+    }
+
+    pub(crate) fn at_most<P: Period>(&self, period: P, bound: P) -> bool {
+        period.ticks() as f32 <= bound.ticks() as f32 * (1.0 + self.tolerance)
+    }
+}
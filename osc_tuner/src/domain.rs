@@ -1,6 +1,12 @@
 use static_assertions as sa;
 use xbounded::{make_bounded, Bounded};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "postcard")]
+use postcard::experimental::max_size::MaxSize;
+
 pub const C0: usize = 12;
 pub const C1: usize = 24;
 pub const C4: usize = 60;
@@ -11,5 +17,8 @@ pub const MIDI_SCALE_20KHZ: f32 = 135.076_23;
 
 make_bounded!(pub MidiOscDomain, f32 : [12..120]);
 
+// A4 frequency in Hz, from baroque to high modern pitch.
+make_bounded!(pub ReferencePitch, f32 : [415..466]);
+
 make_bounded!(pub MidiFilterDomain, f32 : [12..135.076_23]);
 sa::const_assert!(MIDI_SCALE_20KHZ == <MidiFilterDomain as Bounded>::MAX_REP);
@@ -0,0 +1,73 @@
+use crate::{period::MicrosPeriod, progress::Phase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasureError<E> {
+    /// The counter saw no edges at all.
+    NoEdges,
+    /// The period did not complete within the requested timeout.
+    Timeout,
+    /// Readings still moved after [`crate::settling::SettleDelay::Adaptive`]
+    /// took all of its readings.
+    Unsettled,
+    Device(E),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuneError<E, P = MicrosPeriod> {
+    /// The oscillator produced no edges.
+    Dead,
+    Timeout,
+    /// The oscillator did not settle after a DAC write, see
+    /// [`MeasureError::Unsettled`].
+    Unsettled,
+    /// The target period lies beyond what the DAC reaches at `limit`.
+    Unreachable {
+        target: P,
+        limit: Limit,
+    },
+    /// The tuning puts MIDI `note` at a period the period type cannot hold.
+    OutOfRange {
+        note: usize,
+    },
+    /// A reading fell outside the bracket of its neighbours while searching for `target`.
+    NonMonotonic {
+        target: P,
+    },
+    /// The observer cancelled the tune while searching for anchor `index` of
+    /// `phase`. Anchors before it keep their new codes.
+    Cancelled {
+        phase: Phase,
+        index: usize,
+    },
+    Device(E),
+}
+
+impl<E, P> From<MeasureError<E>> for TuneError<E, P> {
+    fn from(error: MeasureError<E>) -> Self {
+        match error {
+            MeasureError::NoEdges => Self::Dead,
+            MeasureError::Timeout => Self::Timeout,
+            MeasureError::Unsettled => Self::Unsettled,
+            MeasureError::Device(error) => Self::Device(error),
+        }
+    }
+}
+
+/// Failure while tuning a temperature bin of a calibration set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureTuneError<E, S, P = MicrosPeriod> {
+    Sensor(S),
+    Tune(TuneError<E, P>),
+}
+
+impl<E, S, P> From<TuneError<E, P>> for TemperatureTuneError<E, S, P> {
+    fn from(error: TuneError<E, P>) -> Self {
+        Self::Tune(error)
+    }
+}
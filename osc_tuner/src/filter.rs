@@ -0,0 +1,213 @@
+use core::{
+    array::from_fn,
+    cell::Cell,
+    future::Future,
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::{float::FloatCore, AsPrimitive, One};
+use uxt::Ux;
+
+use crate::{
+    cache::Cache,
+    config::TuneConfig,
+    domain::{MidiFilterDomain, MidiOscDomain, C0, MIDI_SCALE_20KHZ},
+    error::{MeasureError, TuneError},
+    measurement::MeasurementStrategy,
+    period::{MicrosPeriod, Period},
+    progress::TuneObserver,
+    settling::Settled,
+    table::{dac_from_position, lerp, NoteCurve, TuningTable},
+    tuning::{Edo, TuningSystem},
+    OscfExt, OscfExtPriv, TryOscf,
+};
+
+/// A self-oscillating VCF whose resonance period is measured while a single
+/// DAC sets the cutoff.
+pub trait TryVcf {
+    type DacValue: Copy + 'static + Ux;
+    type Period: Period = MicrosPeriod;
+    type Error;
+
+    fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Self::Period, MeasureError<Self::Error>>>;
+    fn try_set_cutoff_dac(
+        &mut self,
+        value: Self::DacValue,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Cutoff DAC codes at `N` anchors every `STEP` semitones from C0, covering
+/// [`MidiFilterDomain`] up to 20 kHz.
+pub struct CutoffTable<T: Ux, const STEP: usize = 3, const N: usize = 42> {
+    pub anchors: [Cell<T>; N],
+}
+
+impl<T: Ux, const STEP: usize, const N: usize> CutoffTable<T, STEP, N> {
+    pub fn new() -> Self {
+        const {
+            assert!(N >= 2 && STEP >= 1);
+            assert!((C0 + (N - 1) * STEP) as f32 <= MIDI_SCALE_20KHZ);
+            assert!((C0 + N * STEP) as f32 > MIDI_SCALE_20KHZ);
+        };
+        Self {
+            anchors: from_fn(|_| Cell::new(T::default())),
+        }
+    }
+}
+
+impl<T: Ux, const STEP: usize, const N: usize> Default for CutoffTable<T, STEP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ux + Copy, const STEP: usize, const N: usize> CutoffTable<T, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    /// Returns the cutoff DAC code for `note`. Notes past the last anchor
+    /// extend the top segment.
+    pub fn dac_for_cutoff(&self, note: MidiFilterDomain) -> T {
+        dac_from_position(self.position(note.get()))
+    }
+}
+
+impl<T: Ux + Copy, const STEP: usize, const N: usize> CutoffTable<T, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    fn position(&self, note: f32) -> f32 {
+        let at = |i: usize| -> f32 { self.anchors[i].get().into().as_() };
+        let x = (note - C0 as f32) / STEP as f32;
+        let i = (FloatCore::floor(x).max(0.0) as usize).min(N - 2);
+        lerp(at(i), at(i + 1), x - i as f32)
+    }
+}
+
+impl<T: Ux + Copy, const STEP: usize, const N: usize> NoteCurve for CutoffTable<T, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    type DacValue = T;
+
+    fn position_for_note(&self, note: MidiOscDomain) -> f32 {
+        self.position(note.get())
+    }
+
+    fn segment(&self, note: f32) -> (usize, usize) {
+        let x = (note - C0 as f32) / STEP as f32;
+        let i = (FloatCore::floor(x).max(0.0) as usize).min(N - 2);
+        (C0 + i * STEP, C0 + (i + 1) * STEP)
+    }
+}
+
+impl<T: Ux + Copy, const STEP: usize, const N: usize> TuningTable<N> for CutoffTable<T, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    fn note(index: usize) -> usize {
+        C0 + index * STEP
+    }
+
+    fn anchor(&self, index: usize) -> &Cell<T> {
+        &self.anchors[index]
+    }
+}
+
+pub trait VcfExt: TryVcf
+where
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy,
+{
+    /// Fills `table` with the cutoff codes whose resonance hits each anchor
+    /// note in 12-TET at 440 Hz, using the same search as
+    /// `OscfExt::tune_midi_frequencies`. `config.tuning` is not used, see
+    /// [`VcfExt::calibrate_cutoff_in`].
+    fn calibrate_cutoff<const STEP: usize, const N: usize>(
+        &mut self,
+        table: &mut CutoffTable<Self::DacValue, STEP, N>,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl Future<Output = Result<(), TuneError<Self::Error, Self::Period>>>
+    where
+        <Self::DacValue as Ux>::Rep: AsPrimitive<f32>,
+    {
+        async move {
+            self.calibrate_cutoff_in(table, cache, &Edo::default(), config)
+                .await
+        }
+    }
+
+    /// Like `calibrate_cutoff`, but the anchor notes sit where `tuning` puts
+    /// them. `dac_for_cutoff` still interpolates between anchors as 12-TET.
+    fn calibrate_cutoff_in<const STEP: usize, const N: usize>(
+        &mut self,
+        table: &mut CutoffTable<Self::DacValue, STEP, N>,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        tuning: &impl TuningSystem,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl Future<Output = Result<(), TuneError<Self::Error, Self::Period>>>
+    where
+        <Self::DacValue as Ux>::Rep: AsPrimitive<f32>,
+    {
+        async move {
+            let config = config.tuned_to(tuning);
+            Settled::new(&mut Cutoff(self), config.settling)
+                .tune_midi_frequencies_main(table, cache, false, &config)
+                .await
+        }
+    }
+}
+
+// Drives a filter through the oscillator search, the cutoff taking the place
+// of the main DAC.
+struct Cutoff<'a, F: ?Sized>(&'a mut F);
+
+impl<'a, F: TryVcf + ?Sized> TryOscf for Cutoff<'a, F> {
+    type DacValue = F::DacValue;
+    type Period = F::Period;
+    type Error = F::Error;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        self.0.try_get_period(timeout).await
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.0.try_set_cutoff_dac(value).await
+    }
+
+    async fn try_set_offset_dac(&mut self, _value: Self::DacValue) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, F: TryVcf + ?Sized> OscfExt for Cutoff<'a, F> where
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
@@ -0,0 +1,269 @@
+//! [`crate::parts`] on top of embedded-hal-async drivers: SPI DAC chips, an
+//! input-capture timer as the period meter and a delay for settling.
+
+use core::{
+    cell::Cell,
+    future::{poll_fn, Future},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    task::Poll,
+    time::Duration,
+};
+
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+use uxt::{u12, u2, Ux};
+
+use crate::{
+    error::MeasureError,
+    parts::{Dac, PeriodMeter, Settle},
+    period::{MicrosPeriod, Period},
+    voices::WakerSlot,
+};
+
+/// Frame format of a DAC chip, one SPI transaction per write.
+pub trait DacFrame {
+    type Value: Copy + 'static + Ux;
+    type Bytes: AsRef<[u8]>;
+    const CHANNELS: u8;
+
+    fn frame(&self, channel: u8, value: Self::Value) -> Self::Bytes;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Gain {
+    /// Full scale is the reference voltage.
+    #[default]
+    Single,
+    Double,
+}
+
+/// MCP4822 and MCP4922, 12 bits on channels A and B. `buffered` sets the
+/// reference input buffer of the MCP4922 and is ignored by the MCP4822.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Mcp4x22 {
+    pub gain: Gain,
+    pub buffered: bool,
+}
+
+impl DacFrame for Mcp4x22 {
+    type Value = u12;
+    type Bytes = [u8; 2];
+    const CHANNELS: u8 = 2;
+
+    fn frame(&self, channel: u8, value: u12) -> [u8; 2] {
+        let word = (channel as u16) << 15
+            | (self.buffered as u16) << 14
+            | ((self.gain == Gain::Single) as u16) << 13
+            | 1 << 12
+            | u16::from(value);
+        word.to_be_bytes()
+    }
+}
+
+/// DAC8564, 16 bits on four channels, updating the written channel only.
+/// `address` must match the A1 and A0 pins.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Dac8564 {
+    pub address: u2,
+}
+
+impl DacFrame for Dac8564 {
+    type Value = u16;
+    type Bytes = [u8; 3];
+    const CHANNELS: u8 = 4;
+
+    fn frame(&self, channel: u8, value: u16) -> [u8; 3] {
+        let [high, low] = value.to_be_bytes();
+        [
+            u8::from(self.address) << 6 | 0b01 << 4 | channel << 1,
+            high,
+            low,
+        ]
+    }
+}
+
+/// AD5668, 16 bits on eight channels, written and updated at once. Enabling
+/// the internal reference of the -1 and -3 grades is left to the caller.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ad5668;
+
+impl DacFrame for Ad5668 {
+    type Value = u16;
+    type Bytes = [u8; 4];
+    const CHANNELS: u8 = 8;
+
+    fn frame(&self, channel: u8, value: u16) -> [u8; 4] {
+        let word = 0b0011 << 24 | (channel as u32) << 20 | (value as u32) << 4;
+        word.to_be_bytes()
+    }
+}
+
+/// A DAC chip on its SPI device. Its channels are separate [`Dac`]s that
+/// take turns on the device, so one chip can serve several oscillators.
+pub struct DacChip<S, F> {
+    spi: Cell<Option<S>>,
+    waiter: WakerSlot,
+    format: F,
+}
+
+impl<S, F: DacFrame> DacChip<S, F> {
+    pub fn new(spi: S, format: F) -> Self {
+        Self {
+            spi: Cell::new(Some(spi)),
+            waiter: WakerSlot::new(),
+            format,
+        }
+    }
+
+    pub fn channel(&self, index: u8) -> DacChannel<'_, S, F> {
+        assert!(index < F::CHANNELS, "the chip has {} channels", F::CHANNELS);
+        DacChannel { chip: self, index }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.spi
+            .into_inner()
+            .unwrap_or_else(|| panic!("should never happen"))
+    }
+
+    // Waits until the channel writing the device puts it back.
+    async fn lock(&self) -> SpiGuard<'_, S> {
+        poll_fn(|cx| match self.spi.take() {
+            Some(spi) => Poll::Ready(SpiGuard {
+                slot: &self.spi,
+                waiter: &self.waiter,
+                spi: Some(spi),
+            }),
+            None => {
+                self.waiter.register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+// Puts the device back even if the write is dropped halfway.
+struct SpiGuard<'a, S> {
+    slot: &'a Cell<Option<S>>,
+    waiter: &'a WakerSlot,
+    spi: Option<S>,
+}
+
+impl<S> Deref for SpiGuard<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.spi
+            .as_ref()
+            .unwrap_or_else(|| panic!("should never happen"))
+    }
+}
+
+impl<S> DerefMut for SpiGuard<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.spi
+            .as_mut()
+            .unwrap_or_else(|| panic!("should never happen"))
+    }
+}
+
+impl<S> Drop for SpiGuard<'_, S> {
+    fn drop(&mut self) {
+        self.slot.set(self.spi.take());
+        self.waiter.wake();
+    }
+}
+
+pub struct DacChannel<'a, S, F> {
+    chip: &'a DacChip<S, F>,
+    index: u8,
+}
+
+impl<S: SpiDevice, F: DacFrame> Dac for DacChannel<'_, S, F> {
+    type Value = F::Value;
+    type Error = S::Error;
+
+    async fn write(&mut self, value: Self::Value) -> Result<(), Self::Error> {
+        let frame = self.chip.format.frame(self.index, value);
+        self.chip.lock().await.write(frame.as_ref()).await
+    }
+}
+
+/// A free-running timer that latches its count on rising edges of the
+/// oscillator.
+pub trait InputCapture {
+    type Error;
+    /// Timer ticks per second.
+    const TICK_HZ: u32;
+
+    /// Count at the next edge, `None` if none came within `timeout`.
+    fn capture(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Option<u32>, Self::Error>>;
+}
+
+/// Period from the counts at `P::CYCLES + 1` consecutive edges. The timer may
+/// wrap around once per reading.
+#[derive(Debug)]
+pub struct CaptureMeter<C, P = MicrosPeriod> {
+    pub capture: C,
+    _period: PhantomData<P>,
+}
+
+impl<C: InputCapture, P: Period> CaptureMeter<C, P> {
+    pub fn new(capture: C) -> Self {
+        Self {
+            capture,
+            _period: PhantomData,
+        }
+    }
+}
+
+impl<C: InputCapture, P: Period> PeriodMeter for CaptureMeter<C, P> {
+    type Period = P;
+    type Error = C::Error;
+
+    async fn measure_period(&mut self, timeout: Duration) -> Result<P, MeasureError<C::Error>> {
+        let first = self
+            .capture
+            .capture(timeout)
+            .await
+            .map_err(MeasureError::Device)?
+            .ok_or(MeasureError::NoEdges)?;
+        let mut last = first;
+        for _ in 0..P::CYCLES {
+            last = self
+                .capture
+                .capture(timeout)
+                .await
+                .map_err(MeasureError::Device)?
+                .ok_or(MeasureError::Timeout)?;
+        }
+
+        let elapsed = last.wrapping_sub(first) as u64;
+        if elapsed as u128 * 1_000_000_000 > timeout.as_nanos() * C::TICK_HZ as u128 {
+            return Err(MeasureError::Timeout);
+        }
+        let ticks = (elapsed * P::TICK_HZ as u64 + C::TICK_HZ as u64 / 2) / C::TICK_HZ as u64;
+        u32::try_from(ticks)
+            .ok()
+            .and_then(P::from_ticks)
+            .ok_or(MeasureError::Timeout)
+    }
+}
+
+/// Waits a fixed time, rounded to microseconds, for the oscillator to settle.
+#[derive(Debug)]
+pub struct DelaySettle<T> {
+    pub delay: T,
+    pub time: Duration,
+}
+
+impl<T: DelayNs> Settle for DelaySettle<T> {
+    async fn settle(&mut self) {
+        let micros = u32::try_from(self.time.as_micros()).unwrap_or(u32::MAX);
+        self.delay.delay_us(micros).await
+    }
+}
@@ -1,7 +1,3 @@
-use core::num::NonZeroU16;
-
-use const_soft_float::soft_f32::SoftF32;
-
 const TBLSIZE: usize = 16;
 
 const EXP2FT: [u64; TBLSIZE] = [
@@ -23,7 +19,7 @@ const EXP2FT: [u64; TBLSIZE] = [
     0x3ff5ab07dd485429,
 ];
 
-const fn exp2f(mut x: f32) -> f32 {
+pub(crate) const fn exp2f(mut x: f32) -> f32 {
     let redux = f32::from_bits(0x4b400000) / TBLSIZE as f32;
     let p1 = f32::from_bits(0x3f317218);
     let p2 = f32::from_bits(0x3e75fdf0);
@@ -83,19 +79,35 @@ const fn exp2f(mut x: f32) -> f32 {
     (r * ukf) as f32
 }
 
-const fn round(n: f32) -> f32 {
-    SoftF32(n).round().0
+/// Base 2 logarithm of a positive, finite `x`.
+pub(crate) fn log2(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3ff0_0000_0000_0000);
+
+    // ln(m) = 2 * atanh((m - 1) / (m + 1)), |s| <= 1/3 for m in [1, 2).
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let s2 = s * s;
+    let mut term = s;
+    let mut sum = 0.0;
+    let mut k = 1.0;
+    while k < 30.0 {
+        sum += term / k;
+        term *= s2;
+        k += 2.0;
+    }
+    exponent as f64 + 2.0 * sum * core::f64::consts::LOG2_E
+}
+
+/// `2^x` for `x` within the normal `f64` exponent range.
+pub(crate) fn exp2(x: f64) -> f64 {
+    let whole = num_traits::float::FloatCore::floor(x);
+    let scale = f64::from_bits(((whole as i64 + 1023) as u64) << 52);
+    exp2f((x - whole) as f32) as f64 * scale
 }
 
 pub const fn nth_key_frequency(n: f32) -> f32 {
     exp2f((n - 57.0) / 12.0) * 440.0
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-#[repr(transparent)]
-pub struct MicrosPeriod(NonZeroU16);
-
-pub const fn nth_key_period(n: f32) -> MicrosPeriod {
-    let period = round(1000000.0 / nth_key_frequency(n));
-    MicrosPeriod(NonZeroU16::new(period as u16).unwrap())
-}
+pub use crate::period::MicrosPeriod;
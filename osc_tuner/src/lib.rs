@@ -1,113 +1,465 @@
 #![feature(const_fn_floating_point_arithmetic)]
 #![feature(const_float_bits_conv)]
-#![feature(associated_const_equality)]
+//SCRATCH#![feature(associated_const_equality)]
 #![feature(unboxed_closures)]
 #![feature(const_option)]
 #![feature(impl_trait_in_assoc_type)]
 #![feature(const_trait_impl)]
+#![feature(associated_type_defaults)]
 #![no_std]
 
-use core::{cell::Cell, future::Future, ops::Sub};
+#[cfg(feature = "sim")]
+extern crate std;
+
+use core::{
+    convert::Infallible,
+    future::Future,
+    ops::{Add, Sub},
+    time::Duration,
+};
 
-use array_const_fn_init::array_const_fn_init;
 use cache::Cache;
+use config::TuneConfig;
+use error::{Limit, MeasureError, TuneError};
+use measurement::{Expected, MeasurementStrategy};
+use model::{ExpModel, ModelFit};
 use num_integer::Average;
+use progress::{Phase, TuneEvent, TuneObserver};
 use static_assertions as sa;
 
-use domain::{C0, C1, C4, C9};
-use key_frequencies::{nth_key_period, MicrosPeriod};
-use num_traits::One;
-use table::Table;
+use domain::{C4, C9};
+use num_traits::{AsPrimitive, One};
+use period::{MicrosPeriod, Period};
+use report::TuningReport;
+use search::Side;
+use settling::Settled;
+use table::TuningTable;
+use tuning::TuningSystem;
 use uxt::Ux;
 
 use crate::cache::NoCache;
 
 const C4_TO_C9_SIZE: usize = C9 - C4 + 1;
 
+pub mod blocking;
 pub mod cache;
+pub mod calibration;
+pub mod config;
 pub mod domain;
+pub mod error;
+pub mod filter;
+#[cfg(feature = "embedded-hal-async")]
+pub mod hal;
 pub mod key_frequencies;
+pub mod machine;
+pub mod measurement;
+pub mod model;
+pub mod parts;
+pub mod period;
+pub mod pitch;
+pub mod progress;
+pub mod report;
+pub mod scala;
+pub mod search;
+pub mod settling;
+#[cfg(feature = "sim")]
+pub mod sim;
 pub mod table;
-
-const C0_PERIOD: MicrosPeriod = nth_key_period(C0 as f32);
-const C1_PERIOD: MicrosPeriod = nth_key_period(C1 as f32);
-
-const fn index_to_period(i: usize) -> MicrosPeriod {
-    nth_key_period(48.0 + i as f32)
-}
+pub mod tuning;
+pub mod voices;
 
 sa::const_assert!(C4_TO_C9_SIZE == 61);
-const C4_TO_C9_PERIODS: [MicrosPeriod; 61] = array_const_fn_init![index_to_period; 61];
 
 pub trait Oscf {
     type DacValue: Copy + 'static + Ux;
+    type Period: Period = MicrosPeriod;
 
-    fn get_period(&mut self) -> impl Future<Output = MicrosPeriod>;
+    fn get_period(&mut self) -> impl Future<Output = Self::Period>;
     fn set_main_dac(&mut self, value: Self::DacValue) -> impl Future<Output = ()>;
     fn set_offset_dac(&mut self, value: Self::DacValue) -> impl Future<Output = ()>;
+
+    /// Waits `duration` for a [`settling::SettleDelay::Fixed`]. By default the
+    /// time is spent reading and dropping periods until they add up to it;
+    /// oscillators with a timer should wait on that instead.
+    fn delay(&mut self, duration: Duration) -> impl Future<Output = ()> {
+        async move {
+            let mut waited = 0.0;
+            while waited < duration.as_secs_f64() {
+                waited += self.get_period().await.seconds() * Self::Period::CYCLES as f64;
+            }
+        }
+    }
+}
+
+/// Reads the temperature of the oscillator core in °C, for
+/// [`calibration::CalibrationSet`].
+pub trait TemperatureSensor {
+    type Error;
+
+    fn read_temperature(&mut self) -> impl Future<Output = Result<f32, Self::Error>>;
+}
+
+/// Fallible counterpart of [`Oscf`]. Every `Oscf` is a `TryOscf` that never fails.
+pub trait TryOscf {
+    type DacValue: Copy + 'static + Ux;
+    type Period: Period = MicrosPeriod;
+    type Error;
+
+    fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Self::Period, MeasureError<Self::Error>>>;
+    fn try_set_main_dac(
+        &mut self,
+        value: Self::DacValue,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+    fn try_set_offset_dac(
+        &mut self,
+        value: Self::DacValue,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Waits for the oscillator to follow the DAC writes since the last
+    /// reading. A tune calls it once before the first reading after them,
+    /// ahead of [`config::TuneConfig::settling`]. Does nothing by default.
+    fn settle_writes(&mut self) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Fallible [`Oscf::delay`]. `timeout` bounds each reading the default
+    /// takes.
+    fn try_delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), MeasureError<Self::Error>>> {
+        async move {
+            let mut waited = 0.0;
+            while waited < duration.as_secs_f64() {
+                let period = self.try_get_period(timeout).await?;
+                waited += period.seconds() * Self::Period::CYCLES as f64;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<O: Oscf + ?Sized> TryOscf for O {
+    type DacValue = O::DacValue;
+    type Period = O::Period;
+    type Error = Infallible;
+
+    async fn try_get_period(
+        &mut self,
+        _timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        Ok(self.get_period().await)
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.set_main_dac(value).await;
+        Ok(())
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.set_offset_dac(value).await;
+        Ok(())
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        _timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        self.delay(duration).await;
+        Ok(())
+    }
+}
+
+async fn measure<O: TryOscf + ?Sized, S, R>(
+    o: &mut O,
+    config: &TuneConfig<impl MeasurementStrategy, S, R>,
+    expected: Option<Expected<O::Period>>,
+) -> TuneResult<O, O::Period> {
+    Ok(config
+        .measurement
+        .measure(o, config.timeout, expected)
+        .await?)
+}
+
+async fn set_main<O: TryOscf + ?Sized>(o: &mut O, value: O::DacValue) -> TuneResult<O, ()> {
+    o.try_set_main_dac(value).await.map_err(TuneError::Device)
 }
 
-trait OscfExtPriv: Oscf
+async fn set_offset<O: TryOscf + ?Sized>(o: &mut O, value: O::DacValue) -> TuneResult<O, ()> {
+    o.try_set_offset_dac(value).await.map_err(TuneError::Device)
+}
+
+// Target period of anchor `index` under the configured tuning.
+fn note_period<P: Period, E, const N: usize, A: TuningTable<N> + ?Sized, R>(
+    _table: &A,
+    index: usize,
+    config: &TuneConfig<impl MeasurementStrategy, impl TuningSystem, R>,
+) -> Result<P, TuneError<E, P>> {
+    let note = A::note(index);
+    config
+        .tuning
+        .period(note)
+        .ok_or(TuneError::OutOfRange { note })
+}
+
+// Whether `period`, read at the `limit` end of a bracket, lies on the side of
+// `target` that end should. Readings just past it within tolerance settle the
+// search on that end instead.
+fn brackets<P: Period, E, R>(
+    period: P,
+    target: P,
+    limit: Limit,
+    config: &TuneConfig<impl MeasurementStrategy, impl TuningSystem, R>,
+) -> Result<bool, TuneError<E, P>> {
+    let (outside, within) = match limit {
+        Limit::Min => (period < target, config.at_least(period, target)),
+        Limit::Max => (period > target, config.at_most(period, target)),
+    };
+    match (outside, within) {
+        (false, _) => Ok(true),
+        (true, true) => Ok(false),
+        (true, false) => Err(TuneError::Unreachable { target, limit }),
+    }
+}
+
+type TuneResult<O, T> = Result<T, TuneError<<O as TryOscf>::Error, <O as TryOscf>::Period>>;
+
+trait OscfExtPriv: TryOscf
 where
-    <Self::DacValue as Ux>::Rep:
-        Sub<Output = <Self::DacValue as Ux>::Rep> + One + PartialOrd + Average + Copy,
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy,
 {
     async fn async_search(
         &mut self,
         async_get: impl AsyncGetPeriodGen<Self>,
-        mut low: Self::DacValue,
-        mut high: Self::DacValue,
-        target: MicrosPeriod,
-    ) -> Self::DacValue {
+        low: Self::DacValue,
+        high: Self::DacValue,
+        target: Self::Period,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        // Periods shrink as the DAC code grows, so `low` must sit at or above
+        // the target period and `high` at or below it.
+        let low_period = async_get.call(self).call(low, None).await?;
+        if !brackets(low_period, target, Limit::Min, config)? {
+            return Ok(low);
+        }
+
+        let high_period = async_get.call(self).call(high, None).await?;
+        if !brackets(high_period, target, Limit::Max, config)? {
+            return Ok(high);
+        }
+
+        self.async_search_bracket(
+            async_get,
+            (low, low_period),
+            (high, high_period),
+            target,
+            config,
+        )
+        .await
+    }
+
+    async fn async_search_bracket(
+        &mut self,
+        async_get: impl AsyncGetPeriodGen<Self>,
+        (mut low, mut low_period): (Self::DacValue, Self::Period),
+        (mut high, mut high_period): (Self::DacValue, Self::Period),
+        target: Self::Period,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        let mut iteration = 0;
+        let mut moved = [None; 2];
         loop {
             if high.into() - low.into() <= <<Self::DacValue as Ux>::Rep as One>::one() {
-                if async_get.call(self).call(high).await >= target {
-                    return high;
+                if high_period >= target {
+                    return Ok(high);
                 } else {
-                    return low;
+                    return Ok(low);
                 }
             }
 
-            let mid = <Self::DacValue as TryFrom<<Self::DacValue as Ux>::Rep>>::try_from(
-                low.into().average_floor(&high.into()),
-            )
-            .unwrap_or_else(|_| panic!("should never happen"));
+            let mid = search::next_code(
+                config.search,
+                (low, low_period),
+                (high, high_period),
+                target,
+                moved,
+            );
+
+            let expected = Expected {
+                min: high_period,
+                max: low_period,
+            };
+            let period = async_get.call(self).call(mid, Some(expected)).await?;
+            if !config.at_most(period, low_period) || !config.at_least(period, high_period) {
+                return Err(TuneError::NonMonotonic { target });
+            }
 
-            if target >= async_get.call(self).call(mid).await {
+            let side = if target >= period {
                 high = mid;
+                high_period = period;
+                Side::High
             } else {
                 low = mid;
-            }
+                low_period = period;
+                Side::Low
+            };
+            moved = [Some(side), moved[0]];
+            iteration += 1;
+            config.observer.event(TuneEvent::Bracket {
+                iteration,
+                low,
+                high,
+            });
         }
     }
 
     async fn async_search_full(
         &mut self,
         async_get: impl AsyncGetPeriodGen<Self>,
-        target: MicrosPeriod,
-    ) -> Self::DacValue {
+        target: Self::Period,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
         self.async_search(
             async_get,
             <Self::DacValue as Ux>::MIN,
             <Self::DacValue as Ux>::MAX,
             target,
+            config,
         )
         .await
     }
 
+    /// Starts with a one code bracket next to `seed` and doubles it until it
+    /// holds the target, so codes that only drifted a little are found with
+    /// a handful of readings. The step stops growing at half the range, where
+    /// it already reaches either end.
+    async fn async_search_seeded(
+        &mut self,
+        async_get: impl AsyncGetPeriodGen<Self>,
+        seed: Self::DacValue,
+        target: Self::Period,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        let try_from = |x| {
+            <Self::DacValue as TryFrom<<Self::DacValue as Ux>::Rep>>::try_from(x)
+                .unwrap_or_else(|_| panic!("should never happen"))
+        };
+        let min = <Self::DacValue as Ux>::MIN.into();
+        let max = <Self::DacValue as Ux>::MAX.into();
+        let mut step = <<Self::DacValue as Ux>::Rep as One>::one();
+
+        let seed_period = async_get.call(self).call(seed, None).await?;
+        let (mut near, mut near_period) = (seed, seed_period);
+        if seed_period >= target {
+            loop {
+                if near.into() == max {
+                    brackets(near_period, target, Limit::Max, config)?;
+                    return Ok(near);
+                }
+                let far = if max - near.into() <= step {
+                    <Self::DacValue as Ux>::MAX
+                } else {
+                    try_from(near.into() + step)
+                };
+                let far_period = async_get.call(self).call(far, None).await?;
+                if !config.at_most(far_period, near_period) {
+                    return Err(TuneError::NonMonotonic { target });
+                }
+                if far_period <= target {
+                    return self
+                        .async_search_bracket(
+                            async_get,
+                            (near, near_period),
+                            (far, far_period),
+                            target,
+                            config,
+                        )
+                        .await;
+                }
+                (near, near_period) = (far, far_period);
+                if step <= max - min - step {
+                    step = step + step;
+                }
+            }
+        } else {
+            loop {
+                if near.into() == min {
+                    brackets(near_period, target, Limit::Min, config)?;
+                    return Ok(near);
+                }
+                let far = if near.into() - min <= step {
+                    <Self::DacValue as Ux>::MIN
+                } else {
+                    try_from(near.into() - step)
+                };
+                let far_period = async_get.call(self).call(far, None).await?;
+                if !config.at_least(far_period, near_period) {
+                    return Err(TuneError::NonMonotonic { target });
+                }
+                if far_period >= target {
+                    return self
+                        .async_search_bracket(
+                            async_get,
+                            (far, far_period),
+                            (near, near_period),
+                            target,
+                            config,
+                        )
+                        .await;
+                }
+                (near, near_period) = (far, far_period);
+                if step <= max - min - step {
+                    step = step + step;
+                }
+            }
+        }
+    }
+
     async fn async_search_full_cached(
         &mut self,
         async_get: impl AsyncGetPeriodGen<Self>,
-        cache: &impl Cache<Index = Self::DacValue>,
-        target: MicrosPeriod,
-    ) -> Self::DacValue {
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        target: Self::Period,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        cache.begin_search();
         self.async_search_full(
             {
                 impl<
                         'a,
-                        O: Oscf + ?Sized,
+                        O: TryOscf + ?Sized,
                         G: AsyncGetPeriodGen<O>,
-                        C: Cache<Index = O::DacValue>,
+                        C: Cache<Index = O::DacValue, Period = O::Period>,
                     > AsyncGetPeriodGen<O> for Impl<'a, G, C>
                 {
                     type Ret<'s> = impl AsyncGetPeriod<O>
@@ -115,13 +467,13 @@ where
                                 Self: 's, O: 's;
 
                     fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                        move |dac| async move {
+                        move |dac, expected| async move {
                             if let Some(period) = self.1.get(dac) {
-                                return period;
+                                return Ok(period);
                             }
-                            let period = self.0.call(o).call(dac).await;
+                            let period = self.0.call(o).call(dac, expected).await?;
                             self.1.set(dac, period);
-                            period
+                            Ok(period)
                         }
                     }
                 }
@@ -129,67 +481,92 @@ where
                 Impl(async_get, cache)
             },
             target,
+            config,
         )
         .await
     }
 
-    async fn tune_midi_frequencies_impl(
+    /// With `seeded` set every anchor is searched for next to its current
+    /// value and the cache is bypassed.
+    async fn tune_midi_frequencies_impl<const N: usize>(
         &mut self,
-        table: &Table<Self::DacValue>,
-        async_get_c0: impl AsyncGetPeriodGen<Self>,
-        async_get_c1: impl AsyncGetPeriodGen<Self>,
-        async_get_c4_to_c9: impl IndexedAsyncGetPeriodGen<Self> + Copy,
-        cache: &impl Cache<Index = Self::DacValue>,
-    ) {
-        table.c0.set(
-            self.async_search_full_cached(async_get_c0, cache, C0_PERIOD)
-                .await,
-        );
-        table.c1.set(
-            self.async_search_full_cached(async_get_c1, cache, C1_PERIOD)
-                .await,
-        );
-
-        for (i, slot) in table.c4_to_c9_with_filler.iter().enumerate() {
-            slot.set(
-                self.async_search_full_cached(
-                    {
-                        impl<O: Oscf + ?Sized, G: IndexedAsyncGetPeriodGen<O>>
-                            AsyncGetPeriodGen<O> for Impl<G>
-                        {
-                            type Ret<'s> = impl AsyncGetPeriod<O>
-                            where
-                                Self: 's, O: 's;
+        table: &impl TuningTable<N, DacValue = Self::DacValue>,
+        async_get: impl IndexedAsyncGetPeriodGen<Self> + Copy,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        seeded: bool,
+        phase: Phase,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, ()> {
+        for i in 0..N {
+            // Every reading checks for cancellation first.
+            let async_get = {
+                impl<
+                        'a,
+                        O: TryOscf + ?Sized,
+                        G: IndexedAsyncGetPeriodGen<O>,
+                        R: TuneObserver<O::DacValue, O::Period>,
+                    > AsyncGetPeriodGen<O> for Impl<'a, G, R>
+                {
+                    type Ret<'s> = impl AsyncGetPeriod<O>
+                    where
+                        Self: 's, O: 's;
 
-                            fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                                move |dac| async move { self.1.call(o).call(self.0, dac).await }
+                    fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
+                        move |dac, expected| async move {
+                            if self.3.is_cancelled() {
+                                return Err(TuneError::Cancelled {
+                                    phase: self.1,
+                                    index: self.0,
+                                });
                             }
+                            self.2.call(o).call(self.0, dac, expected).await
                         }
-                        struct Impl<G>(usize, G);
-                        Impl(i, async_get_c4_to_c9)
-                    },
-                    cache,
-                    C4_TO_C9_PERIODS[i],
-                )
-                .await,
-            );
+                    }
+                }
+                struct Impl<'a, G, R>(usize, Phase, G, &'a R);
+                Impl(i, phase, async_get, &config.observer)
+            };
+            let target = note_period(table, i, config)?;
+            config.observer.event(TuneEvent::Anchor {
+                phase,
+                index: i,
+                count: N,
+                target,
+            });
+            let dac = if seeded {
+                self.async_search_seeded(async_get, table.anchor(i).get(), target, config)
+                    .await?
+            } else {
+                self.async_search_full_cached(async_get, cache, target, config)
+                    .await?
+            };
+            table.anchor(i).set(dac);
         }
-        table.c4_to_c9_with_filler[C4_TO_C9_SIZE]
-            .set(table.c4_to_c9_with_filler[C4_TO_C9_SIZE - 1].get());
+        table.finish();
+        Ok(())
     }
 
-    async fn tune_midi_frequencies_single(
+    async fn tune_midi_frequencies_single<const N: usize>(
         &mut self,
-        table: &Table<Self::DacValue>,
+        table: &impl TuningTable<N, DacValue = Self::DacValue>,
         async_get: impl AsyncGetPeriodGen<Self> + Copy,
-        cache: &impl Cache<Index = Self::DacValue>,
-    ) {
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        seeded: bool,
+        phase: Phase,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, ()> {
         self.tune_midi_frequencies_impl(
             table,
-            async_get,
-            async_get,
             {
-                impl<O: Oscf + ?Sized, G: AsyncGetPeriodGen<O>>
+                impl<O: TryOscf + ?Sized, G: AsyncGetPeriodGen<O>>
                     IndexedAsyncGetPeriodGen<O> for Impl<G>
                 {
                     type Ret<'s> = impl IndexedAsyncGetPeriod<O>
@@ -197,7 +574,9 @@ where
                         Self: 's, O: 's;
 
                     fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                        move |_, dac| async move { self.0.call(o).call(dac).await }
+                        move |_, dac, expected| async move {
+                            self.0.call(o).call(dac, expected).await
+                        }
                     }
                 }
                 #[derive(Clone, Copy)]
@@ -205,11 +584,22 @@ where
                 Impl(async_get)
             },
             cache,
+            seeded,
+            phase,
+            config,
         )
-        .await;
+        .await
     }
 
-    async fn find_ratio(&mut self) -> Self::DacValue {
+    async fn find_ratio(
+        &mut self,
+        seed: Option<Self::DacValue>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
         let try_from = |x| {
             <Self::DacValue as TryFrom<<Self::DacValue as Ux>::Rep>>::try_from(x)
                 .unwrap_or_else(|_| panic!("should never happen"))
@@ -222,169 +612,373 @@ where
 
         let main_dac_target = try_from(zero_rep.average_floor(&max_rep));
 
-        self.set_offset_dac(zero).await;
-        self.set_main_dac(main_dac_target).await;
+        set_offset(self, zero).await?;
+        set_main(self, main_dac_target).await?;
 
-        let period = self.get_period().await;
+        let period = measure(self, config, None).await?;
 
         let main_dac_target_minus_one = try_from(main_dac_target.into() - one_rep);
 
-        self.set_main_dac(main_dac_target_minus_one).await;
-        self.async_search_full(
+        set_main(self, main_dac_target_minus_one).await?;
+        let async_get = {
+            impl<
+                    'a,
+                    O: TryOscf + ?Sized,
+                    M: MeasurementStrategy,
+                    S,
+                    R: TuneObserver<O::DacValue, O::Period>,
+                > AsyncGetPeriodGen<O> for Impl<'a, M, S, R>
             {
-                impl<O: Oscf + ?Sized> AsyncGetPeriodGen<O> for Impl {
-                    type Ret<'s> = impl AsyncGetPeriod<O>
-                    where
-                        Self: 's, O: 's;
-
-                    fn call<'s>(&self, o: &'s mut O) -> Self::Ret<'s> {
-                        move |dac| async move {
-                            o.set_offset_dac(dac).await;
-                            o.get_period().await
+                type Ret<'s> = impl AsyncGetPeriod<O>
+                where
+                    Self: 's, O: 's;
+
+                fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
+                    move |dac, expected| async move {
+                        if self.0.observer.is_cancelled() {
+                            return Err(TuneError::Cancelled {
+                                phase: Phase::Ratio,
+                                index: 0,
+                            });
                         }
+                        set_offset(o, dac).await?;
+                        measure(o, self.0, expected).await
                     }
                 }
-                struct Impl;
-                Impl
-            },
-            period,
-        )
-        .await
-    }
-
-    async fn tune_midi_frequencies_offset(
-        &mut self,
-        main_table: &Table<Self::DacValue>,
-        offset_table: &Table<Self::DacValue>,
-    ) {
-        impl<'a, O: Oscf + ?Sized> AsyncGetPeriodGen<O> for Impl<'a, O::DacValue> {
-            type Ret<'s> = impl AsyncGetPeriod<O>
-            where
-                Self: 's, O: 's;
-
-            fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                move |dac| async move {
-                    o.set_main_dac(self.0.get()).await;
-                    o.set_offset_dac(dac).await;
-                    o.get_period().await
-                }
             }
+            struct Impl<'a, M, S, R>(&'a TuneConfig<M, S, R>);
+            Impl(config)
+        };
+        config.observer.event(TuneEvent::Anchor {
+            phase: Phase::Ratio,
+            index: 0,
+            count: 1,
+            target: period,
+        });
+        match seed {
+            Some(seed) => {
+                self.async_search_seeded(async_get, seed, period, config)
+                    .await
+            }
+            None => self.async_search_full(async_get, period, config).await,
         }
-        struct Impl<'a, D>(&'a Cell<D>);
+    }
 
+    async fn tune_midi_frequencies_offset<
+        const N: usize,
+        A: TuningTable<N, DacValue = Self::DacValue>,
+    >(
+        &mut self,
+        main_table: &A,
+        offset_table: &A,
+        seeded: bool,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, ()> {
         self.tune_midi_frequencies_impl(
             offset_table,
-            Impl(&main_table.c0),
-            Impl(&main_table.c1),
             {
-                impl<'a, O: Oscf + ?Sized> IndexedAsyncGetPeriodGen<O> for Impl<'a, O::DacValue> {
+                impl<
+                        'a,
+                        O: TryOscf + ?Sized,
+                        const N: usize,
+                        A: TuningTable<N, DacValue = O::DacValue>,
+                        M: MeasurementStrategy,
+                        S,
+                        R,
+                    > IndexedAsyncGetPeriodGen<O> for Impl<'a, N, A, M, S, R>
+                {
                     type Ret<'s> = impl IndexedAsyncGetPeriod<O>
                     where
                         Self: 's, O: 's;
 
                     fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                        move |i: usize, dac| async move {
-                            o.set_main_dac(self.0.c4_to_c9_with_filler[i].get()).await;
-                            o.set_offset_dac(dac).await;
-                            o.get_period().await
+                        move |i: usize, dac, expected| async move {
+                            set_main(o, self.0.anchor(i).get()).await?;
+                            set_offset(o, dac).await?;
+                            measure(o, self.1, expected).await
                         }
                     }
                 }
-                #[derive(Clone, Copy)]
-                struct Impl<'a, D: Ux>(&'a Table<D>);
-                Impl(main_table)
+                struct Impl<'a, const N: usize, A, M, S, R>(&'a A, &'a TuneConfig<M, S, R>);
+                impl<'a, const N: usize, A, M, S, R> Clone for Impl<'a, N, A, M, S, R> {
+                    fn clone(&self) -> Self {
+                        *self
+                    }
+                }
+                impl<'a, const N: usize, A, M, S, R> Copy for Impl<'a, N, A, M, S, R> {}
+                Impl::<N, _, _, _, _>(main_table, config)
             },
             &NoCache::new(),
+            seeded,
+            Phase::Offset,
+            config,
         )
         .await
     }
 
-    async fn tune_midi_frequencies(
+    async fn tune_midi_frequencies_main<const N: usize>(
         &mut self,
-        main_table: &mut Table<Self::DacValue>,
-        offset_table: &mut Table<Self::DacValue>,
-        cache: &impl Cache<Index = Self::DacValue>,
-    ) -> Self::DacValue {
-        self.set_offset_dac(Default::default()).await;
+        main_table: &impl TuningTable<N, DacValue = Self::DacValue>,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        seeded: bool,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, ()> {
         self.tune_midi_frequencies_single(
             main_table,
             {
-                impl<O: Oscf + ?Sized> AsyncGetPeriodGen<O> for Impl {
+                impl<'a, O: TryOscf + ?Sized, M: MeasurementStrategy, S, R> AsyncGetPeriodGen<O>
+                    for Impl<'a, M, S, R>
+                {
                     type Ret<'s> = impl AsyncGetPeriod<O>
-                    where O : 's;
+                    where Self: 's, O : 's;
                     fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s> {
-                        move |dac| async move {
-                            o.set_main_dac(dac).await;
-                            o.get_period().await
+                        move |dac, expected| async move {
+                            set_main(o, dac).await?;
+                            measure(o, self.0, expected).await
                         }
                     }
                 }
-                #[derive(Copy, Clone)]
-                struct Impl;
-                Impl
+                struct Impl<'a, M, S, R>(&'a TuneConfig<M, S, R>);
+                impl<'a, M, S, R> Clone for Impl<'a, M, S, R> {
+                    fn clone(&self) -> Self {
+                        *self
+                    }
+                }
+                impl<'a, M, S, R> Copy for Impl<'a, M, S, R> {}
+                Impl(config)
             },
             cache,
+            seeded,
+            Phase::Main,
+            config,
         )
-        .await;
-        self.tune_midi_frequencies_offset(main_table, offset_table)
-            .await;
-        self.find_ratio().await
+        .await
+    }
+
+    async fn tune_midi_frequencies<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
+        &mut self,
+        main_table: &mut A,
+        offset_table: &mut A,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        let o = &mut Settled::new(self, config.settling);
+        set_offset(o, Default::default()).await?;
+        o.tune_midi_frequencies_main(main_table, cache, false, config)
+            .await?;
+        o.tune_midi_frequencies_offset::<N, A>(main_table, offset_table, false, config)
+            .await?;
+        o.find_ratio(None, config).await
+    }
+
+    async fn retune_midi_frequencies<
+        const N: usize,
+        A: TuningTable<N, DacValue = Self::DacValue>,
+    >(
+        &mut self,
+        main_table: &mut A,
+        offset_table: &mut A,
+        ratio: Self::DacValue,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> TuneResult<Self, Self::DacValue> {
+        let o = &mut Settled::new(self, config.settling);
+        set_offset(o, Default::default()).await?;
+        o.tune_midi_frequencies_main(main_table, &NoCache::new(), true, config)
+            .await?;
+        o.tune_midi_frequencies_offset::<N, A>(main_table, offset_table, true, config)
+            .await?;
+        o.find_ratio(Some(ratio), config).await
     }
 }
 
 impl<F: OscfExt + ?Sized> OscfExtPriv for F where
-    <Self::DacValue as Ux>::Rep:
-        Sub<Output = <Self::DacValue as Ux>::Rep> + One + PartialOrd + Average + Copy
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
 {
 }
 
-pub trait OscfExt: Oscf
+pub trait OscfExt: TryOscf
 where
-    <Self::DacValue as Ux>::Rep:
-        Sub<Output = <Self::DacValue as Ux>::Rep> + One + PartialOrd + Average + Copy,
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy,
 {
-    fn tune_midi_frequencies(
+    /// Fills either a compact [`table::Table`] or a [`table::NoteTable`] pair.
+    fn tune_midi_frequencies<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
         &mut self,
-        main_table: &mut Table<Self::DacValue>,
-        offset_table: &mut Table<Self::DacValue>,
-        cache: &impl Cache<Index = Self::DacValue>,
-    ) -> impl core::future::Future<Output = Self::DacValue> {
-        <Self as OscfExtPriv>::tune_midi_frequencies(self, main_table, offset_table, cache)
+        main_table: &mut A,
+        offset_table: &mut A,
+        cache: &impl Cache<Index = Self::DacValue, Period = Self::Period>,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl core::future::Future<
+        Output = Result<Self::DacValue, TuneError<Self::Error, Self::Period>>,
+    > {
+        <Self as OscfExtPriv>::tune_midi_frequencies::<N, A>(
+            self,
+            main_table,
+            offset_table,
+            cache,
+            config,
+        )
+    }
+
+    /// Tunes a table pair and ratio from a previous run again, searching
+    /// next to every previous value instead of across the whole DAC range.
+    fn retune_midi_frequencies<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
+        &mut self,
+        main_table: &mut A,
+        offset_table: &mut A,
+        ratio: Self::DacValue,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl core::future::Future<
+        Output = Result<Self::DacValue, TuneError<Self::Error, Self::Period>>,
+    > {
+        <Self as OscfExtPriv>::retune_midi_frequencies::<N, A>(
+            self,
+            main_table,
+            offset_table,
+            ratio,
+            config,
+        )
+    }
+
+    /// Measures `K` codes spread across the main DAC range and fits an
+    /// [`ExpModel`] to those that did not time out, see [`model`]. A
+    /// cancelled fit stops before reading `index` as [`Phase::Main`].
+    fn fit_model<const K: usize>(
+        &mut self,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl core::future::Future<Output = TuneResult<Self, ModelFit<K>>>
+    where
+        <Self::DacValue as Ux>::Rep: AsPrimitive<f64>,
+        f64: AsPrimitive<<Self::DacValue as Ux>::Rep>,
+    {
+        model::fit(self, config)
+    }
+
+    /// Fills a table pair from `model` instead of searching every anchor,
+    /// only the ratio is still searched for. The observer sees the ratio
+    /// search, then each anchor pair as [`Phase::Main`], and may cancel
+    /// before any of them.
+    fn tune_from_model<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
+        &mut self,
+        model: &ExpModel,
+        main_table: &mut A,
+        offset_table: &mut A,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl core::future::Future<
+        Output = Result<Self::DacValue, TuneError<Self::Error, Self::Period>>,
+    >
+    where
+        <Self::DacValue as Ux>::Rep: AsPrimitive<f64>,
+        f64: AsPrimitive<<Self::DacValue as Ux>::Rep>,
+    {
+        model::apply(self, model, main_table, offset_table, config)
+    }
+
+    /// Re-measures every anchor of a tuned table pair and checks it against
+    /// `threshold` cents.
+    fn verify_midi_frequencies<const N: usize, A: TuningTable<N, DacValue = Self::DacValue>>(
+        &mut self,
+        main_table: &A,
+        offset_table: &A,
+        threshold: f32,
+        config: &TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<Self::DacValue, Self::Period>,
+        >,
+    ) -> impl core::future::Future<Output = TuneResult<Self, TuningReport<Self::Period, N>>> {
+        report::verify(self, main_table, offset_table, threshold, config)
     }
 }
 
-trait AsyncGetPeriod<O: Oscf + ?Sized>: FnOnce<(O::DacValue,)> {
-    type Ret: Future<Output = MicrosPeriod>;
-    fn call(self, value: O::DacValue) -> Self::Ret;
+trait AsyncGetPeriod<O: TryOscf + ?Sized>: FnOnce<(O::DacValue, Option<Expected<O::Period>>)> {
+    type Ret: Future<Output = TuneResult<O, O::Period>>;
+    fn call(self, value: O::DacValue, expected: Option<Expected<O::Period>>) -> Self::Ret;
 }
 
-trait IndexedAsyncGetPeriod<O: Oscf + ?Sized>: FnOnce<(usize, O::DacValue)> {
-    type Ret: Future<Output = MicrosPeriod>;
-    fn call(self, index: usize, value: O::DacValue) -> Self::Ret;
+trait IndexedAsyncGetPeriod<O: TryOscf + ?Sized>:
+    FnOnce<(usize, O::DacValue, Option<Expected<O::Period>>)>
+{
+    type Ret: Future<Output = TuneResult<O, O::Period>>;
+    fn call(
+        self,
+        index: usize,
+        value: O::DacValue,
+        expected: Option<Expected<O::Period>>,
+    ) -> Self::Ret;
 }
 
-impl<O: Oscf + ?Sized, I: Future<Output = MicrosPeriod>, F: FnOnce<(O::DacValue,), Output = I>>
-    AsyncGetPeriod<O> for F
+impl<
+        O: TryOscf + ?Sized,
+        I: Future<Output = TuneResult<O, O::Period>>,
+        F: FnOnce<(O::DacValue, Option<Expected<O::Period>>), Output = I>,
+    > AsyncGetPeriod<O> for F
 {
     type Ret = Self::Output;
-    fn call(self, value: O::DacValue) -> Self::Ret {
-        self(value)
+    fn call(self, value: O::DacValue, expected: Option<Expected<O::Period>>) -> Self::Ret {
+        self(value, expected)
     }
 }
 
 impl<
-        O: Oscf + ?Sized,
-        I: Future<Output = MicrosPeriod>,
-        F: FnOnce<(usize, O::DacValue), Output = I>,
+        O: TryOscf + ?Sized,
+        I: Future<Output = TuneResult<O, O::Period>>,
+        F: FnOnce<(usize, O::DacValue, Option<Expected<O::Period>>), Output = I>,
     > IndexedAsyncGetPeriod<O> for F
 {
     type Ret = Self::Output;
-    fn call(self, index: usize, value: O::DacValue) -> Self::Ret {
-        self(index, value)
+    fn call(
+        self,
+        index: usize,
+        value: O::DacValue,
+        expected: Option<Expected<O::Period>>,
+    ) -> Self::Ret {
+        self(index, value, expected)
     }
 }
 
-trait AsyncGetPeriodGen<O: Oscf + ?Sized> {
+trait AsyncGetPeriodGen<O: TryOscf + ?Sized> {
     type Ret<'s>: AsyncGetPeriod<O>
     where
         Self: 's,
@@ -393,7 +987,7 @@ trait AsyncGetPeriodGen<O: Oscf + ?Sized> {
     fn call<'s>(&'s self, o: &'s mut O) -> Self::Ret<'s>;
 }
 
-trait IndexedAsyncGetPeriodGen<O: Oscf + ?Sized> {
+trait IndexedAsyncGetPeriodGen<O: TryOscf + ?Sized> {
     type Ret<'s>: IndexedAsyncGetPeriod<O>
     where
         Self: 's,
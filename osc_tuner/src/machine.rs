@@ -0,0 +1,195 @@
+//! Tuning as a state machine that is stepped by hand, e.g. from a
+//! timer-capture interrupt. It runs the same code as
+//! [`OscfExt::tune_midi_frequencies`], so the results are bit-identical.
+
+use core::{
+    cell::Cell,
+    convert::Infallible,
+    future::{poll_fn, Future},
+    ops::{Add, Sub},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::One;
+use pin_project_lite::pin_project;
+use uxt::Ux;
+
+use crate::{
+    cache::Cache,
+    config::TuneConfig,
+    error::{MeasureError, TuneError},
+    measurement::MeasurementStrategy,
+    period::{MicrosPeriod, Period},
+    progress::TuneObserver,
+    table::TuningTable,
+    tuning::TuningSystem,
+    OscfExt, TryOscf,
+};
+
+/// What the caller has to do before the next [`TuneMachine::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action<D, T> {
+    SetMainDac(D),
+    SetOffsetDac(D),
+    /// Read a period within the timeout and pass it to the next step.
+    Measure(Duration),
+    Done(T),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Request<D> {
+    Main(D),
+    Offset(D),
+    Measure(Duration),
+}
+
+/// Carries actions and readings between a [`TuneMachine`] and the tune it
+/// runs. `E` is the error of the caller's period readings.
+pub struct Link<D, P = MicrosPeriod, E = Infallible> {
+    request: Cell<Option<Request<D>>>,
+    reading: Cell<Option<Result<P, MeasureError<E>>>>,
+}
+
+impl<D, P, E> Default for Link<D, P, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D, P, E> Link<D, P, E> {
+    pub const fn new() -> Self {
+        Self {
+            request: Cell::new(None),
+            reading: Cell::new(None),
+        }
+    }
+}
+
+impl<D: Copy + 'static + Ux, P: Period, E> Link<D, P, E>
+where
+    D::Rep: Add<Output = D::Rep> + Sub<Output = D::Rep> + One + PartialOrd + Average + Copy,
+{
+    /// Machine for [`OscfExt::tune_midi_frequencies`]. Pin it before
+    /// stepping, e.g. with [`core::pin::pin`].
+    pub fn tune_midi_frequencies<'a, const N: usize, A: TuningTable<N, DacValue = D>>(
+        &'a self,
+        main_table: &'a mut A,
+        offset_table: &'a mut A,
+        cache: &'a impl Cache<Index = D, Period = P>,
+        config: &'a TuneConfig<
+            impl MeasurementStrategy,
+            impl TuningSystem,
+            impl TuneObserver<D, P>,
+        >,
+    ) -> TuneMachine<'a, D, P, E, impl Future<Output = Result<D, TuneError<E, P>>> + 'a> {
+        TuneMachine {
+            link: self,
+            future: async move {
+                Port(self)
+                    .tune_midi_frequencies(main_table, offset_table, cache, config)
+                    .await
+            },
+            output: None,
+        }
+    }
+}
+
+pin_project! {
+    pub struct TuneMachine<'a, D, P, E, F: Future> {
+        link: &'a Link<D, P, E>,
+        #[pin]
+        future: F,
+        output: Option<F::Output>,
+    }
+}
+
+impl<'a, D, P, E, F: Future> TuneMachine<'a, D, P, E, F>
+where
+    F::Output: Clone,
+{
+    /// Runs the tune up to its next action. `last` answers the previous
+    /// action: the reading for [`Action::Measure`], or
+    /// `Some(Err(MeasureError::Device(error)))` when a DAC write failed.
+    /// Once the tune is done, every step returns the same [`Action::Done`].
+    pub fn step(
+        self: Pin<&mut Self>,
+        last: Option<Result<P, MeasureError<E>>>,
+    ) -> Action<D, F::Output> {
+        let this = self.project();
+        if let Some(output) = this.output {
+            return Action::Done(output.clone());
+        }
+        this.link.reading.set(last);
+        if let Poll::Ready(output) = this.future.poll(&mut Context::from_waker(Waker::noop())) {
+            *this.output = Some(output.clone());
+            return Action::Done(output);
+        }
+        match this.link.request.take() {
+            Some(Request::Main(value)) => Action::SetMainDac(value),
+            Some(Request::Offset(value)) => Action::SetOffsetDac(value),
+            Some(Request::Measure(timeout)) => Action::Measure(timeout),
+            None => panic!("should never happen"),
+        }
+    }
+}
+
+// The oscillator the tune sees. Every call hands its request to the machine
+// and waits until the machine has returned it as an action.
+struct Port<'a, D, P, E>(&'a Link<D, P, E>);
+
+impl<'a, D: Copy, P, E> Port<'a, D, P, E> {
+    async fn request(&self, request: Request<D>) {
+        self.0.request.set(Some(request));
+        poll_fn(|_| {
+            if self.0.request.get().is_some() {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await
+    }
+
+    // Outcome of the DAC write just returned as an action.
+    fn written(&self) -> Result<(), E> {
+        match self.0.reading.take() {
+            Some(Err(MeasureError::Device(error))) => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a, D: Copy + 'static + Ux, P: Period, E> TryOscf for Port<'a, D, P, E> {
+    type DacValue = D;
+    type Period = P;
+    type Error = E;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        self.request(Request::Measure(timeout)).await;
+        self.0
+            .reading
+            .take()
+            .unwrap_or_else(|| panic!("Action::Measure must be answered with a reading"))
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.request(Request::Main(value)).await;
+        self.written()
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.request(Request::Offset(value)).await;
+        self.written()
+    }
+}
+
+impl<'a, D: Copy + 'static + Ux, P: Period, E> OscfExt for Port<'a, D, P, E> where
+    D::Rep: Add<Output = D::Rep> + Sub<Output = D::Rep> + One + PartialOrd + Average + Copy
+{
+}
@@ -0,0 +1,184 @@
+use core::{array::from_fn, future::Future, time::Duration};
+
+use crate::{
+    error::MeasureError,
+    period::{MicrosPeriod, Period},
+    TryOscf,
+};
+
+/// Range a reading is expected to fall in, known while bisecting from the
+/// periods measured at both ends of the bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expected<P = MicrosPeriod> {
+    pub min: P,
+    pub max: P,
+}
+
+impl<P: Period> Expected<P> {
+    /// Relative distance of `period` outside the range, `0.0` when inside.
+    pub fn distance(&self, period: P) -> f32 {
+        let period = period.ticks() as f32;
+        let min = self.min.ticks() as f32;
+        let max = self.max.ticks() as f32;
+        if period < min {
+            (min - period) / min
+        } else if period > max {
+            (period - max) / max
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Turns one or more raw `try_get_period` readings into the period the search acts on.
+pub trait MeasurementStrategy {
+    fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        expected: Option<Expected<O::Period>>,
+    ) -> impl Future<Output = Result<O::Period, MeasureError<O::Error>>>;
+}
+
+impl<T: MeasurementStrategy + ?Sized> MeasurementStrategy for &T {
+    fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        expected: Option<Expected<O::Period>>,
+    ) -> impl Future<Output = Result<O::Period, MeasureError<O::Error>>> {
+        (**self).measure(o, timeout, expected)
+    }
+}
+
+/// Trusts every reading.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Single;
+
+impl MeasurementStrategy for Single {
+    async fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        _expected: Option<Expected<O::Period>>,
+    ) -> Result<O::Period, MeasureError<O::Error>> {
+        o.try_get_period(timeout).await
+    }
+}
+
+/// Rounded mean of `N` readings.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Mean<const N: usize>;
+
+impl<const N: usize> MeasurementStrategy for Mean<N> {
+    async fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        _expected: Option<Expected<O::Period>>,
+    ) -> Result<O::Period, MeasureError<O::Error>> {
+        let readings = read::<O, N>(o, timeout).await?;
+        Ok(mean(&readings))
+    }
+}
+
+/// Median of `N` readings.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Median<const N: usize>;
+
+impl<const N: usize> MeasurementStrategy for Median<N> {
+    async fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        _expected: Option<Expected<O::Period>>,
+    ) -> Result<O::Period, MeasureError<O::Error>> {
+        let mut readings = read::<O, N>(o, timeout).await?;
+        readings.sort_unstable();
+        Ok(readings[N / 2])
+    }
+}
+
+/// Mean of `N` readings after dropping the `TRIM` lowest and highest ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TrimmedMean<const N: usize, const TRIM: usize>;
+
+impl<const N: usize, const TRIM: usize> MeasurementStrategy for TrimmedMean<N, TRIM> {
+    async fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        _expected: Option<Expected<O::Period>>,
+    ) -> Result<O::Period, MeasureError<O::Error>> {
+        const { assert!(2 * TRIM < N) };
+        let mut readings = read::<O, N>(o, timeout).await?;
+        readings.sort_unstable();
+        Ok(mean(&readings[TRIM..N - TRIM]))
+    }
+}
+
+/// Re-measures with `inner` up to `RETRIES` times while the result lies more
+/// than `threshold` (relative) outside the expected range, then settles for
+/// the closest one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive<S, const RETRIES: usize> {
+    pub inner: S,
+    pub threshold: f32,
+}
+
+impl<S: Default, const RETRIES: usize> Default for Adaptive<S, RETRIES> {
+    fn default() -> Self {
+        Self {
+            inner: S::default(),
+            threshold: 0.002,
+        }
+    }
+}
+
+impl<S: MeasurementStrategy, const RETRIES: usize> MeasurementStrategy for Adaptive<S, RETRIES> {
+    async fn measure<O: TryOscf + ?Sized>(
+        &self,
+        o: &mut O,
+        timeout: Duration,
+        expected: Option<Expected<O::Period>>,
+    ) -> Result<O::Period, MeasureError<O::Error>> {
+        let mut best = self.inner.measure(o, timeout, expected).await?;
+        let Some(expected) = expected else {
+            return Ok(best);
+        };
+
+        let mut best_distance = expected.distance(best);
+        for _ in 0..RETRIES {
+            if best_distance <= self.threshold {
+                break;
+            }
+            let period = self.inner.measure(o, timeout, Some(expected)).await?;
+            let distance = expected.distance(period);
+            if distance < best_distance {
+                best = period;
+                best_distance = distance;
+            }
+        }
+        Ok(best)
+    }
+}
+
+async fn read<O: TryOscf + ?Sized, const N: usize>(
+    o: &mut O,
+    timeout: Duration,
+) -> Result<[O::Period; N], MeasureError<O::Error>> {
+    const { assert!(N > 0) };
+    let mut readings = [None; N];
+    for reading in readings.iter_mut() {
+        *reading = Some(o.try_get_period(timeout).await?);
+    }
+    Ok(from_fn(|i| {
+        readings[i].unwrap_or_else(|| panic!("should never happen"))
+    }))
+}
+
+fn mean<P: Period>(readings: &[P]) -> P {
+    let n = readings.len() as u64;
+    let sum: u64 = readings.iter().map(|period| period.ticks() as u64).sum();
+    P::from_ticks(((sum + n / 2) / n) as u32).unwrap_or_else(|| panic!("should never happen"))
+}
@@ -0,0 +1,253 @@
+//! Quick tuning from a handful of readings fitted to an exponential model of
+//! the oscillator, instead of one search per anchor.
+
+use core::ops::{Add, Sub};
+
+use num_integer::Average;
+use num_traits::{float::FloatCore, AsPrimitive, One};
+use uxt::Ux;
+
+use crate::{
+    config::TuneConfig,
+    error::{Limit, TuneError},
+    key_frequencies::{exp2, log2},
+    measure,
+    measurement::MeasurementStrategy,
+    note_period,
+    period::Period,
+    progress::{Phase, TuneEvent, TuneObserver},
+    set_main, set_offset,
+    settling::Settled,
+    table::TuningTable,
+    tuning::TuningSystem,
+    OscfExtPriv, TryOscf, TuneResult,
+};
+
+/// Frequency of an exponential VCO against its main DAC code, with a fixed
+/// reset time added to every period that flattens the top octaves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpModel {
+    /// log2 of the frequency in Hz at code 0, reset time aside.
+    pub log2_base: f64,
+    pub octaves_per_code: f64,
+    /// In seconds.
+    pub reset_time: f64,
+}
+
+impl ExpModel {
+    pub fn frequency(&self, code: f64) -> f64 {
+        1.0 / (exp2(-(self.log2_base + self.octaves_per_code * code)) + self.reset_time)
+    }
+
+    /// Fractional code that plays `frequency`, `None` when the reset time
+    /// alone is longer than its period.
+    pub fn code_for_frequency(&self, frequency: f64) -> Option<f64> {
+        let period = 1.0 / frequency - self.reset_time;
+        if !period.is_finite() || period <= 0.0 || self.octaves_per_code == 0.0 {
+            return None;
+        }
+        Some((-log2(period) - self.log2_base) / self.octaves_per_code)
+    }
+
+    // Least squares line through the log frequencies once `reset_time` is
+    // taken off the periods, with its sum of squared errors.
+    fn fit_line(points: &[(f64, f64)], reset_time: f64) -> (Self, f64) {
+        let n = points.len() as f64;
+        let log2_frequency = |period: f64| -log2(period - reset_time);
+        let (sum_code, sum_log) = points.iter().fold((0.0, 0.0), |(c, y), &(code, period)| {
+            (c + code, y + log2_frequency(period))
+        });
+        let (mean_code, mean_log) = (sum_code / n, sum_log / n);
+        let (covariance, variance) =
+            points
+                .iter()
+                .fold((0.0, 0.0), |(cov, var), &(code, period)| {
+                    let dc = code - mean_code;
+                    (
+                        cov + dc * (log2_frequency(period) - mean_log),
+                        var + dc * dc,
+                    )
+                });
+        let octaves_per_code = covariance / variance;
+        let model = Self {
+            log2_base: mean_log - octaves_per_code * mean_code,
+            octaves_per_code,
+            reset_time,
+        };
+        let error = points
+            .iter()
+            .map(|&(code, period)| {
+                let e = log2_frequency(period) - model.log2_base - octaves_per_code * code;
+                e * e
+            })
+            .sum();
+        (model, error)
+    }
+
+    /// Fits scale and offset in the log domain, and the reset time by golden
+    /// section search over what is left.
+    pub fn fit(points: &[(f64, f64)]) -> Self {
+        let shortest = points
+            .iter()
+            .map(|&(_, period)| period)
+            .fold(f64::INFINITY, f64::min);
+        let error = |reset_time| Self::fit_line(points, reset_time).1;
+
+        const RATIO: f64 = 0.618_033_988_749_894_8;
+        let (mut a, mut b) = (0.0, shortest * 0.9);
+        for _ in 0..64 {
+            let c = b - (b - a) * RATIO;
+            let d = a + (b - a) * RATIO;
+            if error(c) <= error(d) {
+                b = d;
+            } else {
+                a = c;
+            }
+        }
+        Self::fit_line(points, (a + b) / 2.0).0
+    }
+}
+
+/// Model fitted to readings at the centres of `K` equal slices of the main
+/// DAC range. Readings that time out are left out while at least 3 remain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelFit<const K: usize> {
+    pub model: ExpModel,
+    /// Pitch of the model against each reading in cents, positive when the
+    /// model is sharp, `None` where the reading timed out. Large residuals
+    /// mean the oscillator is not exponential enough and should get the full
+    /// search instead.
+    pub residuals: [Option<f32>; K],
+}
+
+impl<const K: usize> ModelFit<K> {
+    pub fn max_residual(&self) -> f32 {
+        self.residuals
+            .iter()
+            .flatten()
+            .map(|r| FloatCore::abs(*r))
+            .fold(0.0, f32::max)
+    }
+}
+
+pub(crate) async fn fit<O: TryOscf + ?Sized, const K: usize>(
+    o: &mut O,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<O::DacValue, O::Period>,
+    >,
+) -> TuneResult<O, ModelFit<K>>
+where
+    <O::DacValue as Ux>::Rep: AsPrimitive<f64>,
+    f64: AsPrimitive<<O::DacValue as Ux>::Rep>,
+{
+    const { assert!(K >= 3) };
+    let max: f64 = O::DacValue::MAX.into().as_();
+
+    let o = &mut Settled::new(o, config.settling);
+    set_offset(o, Default::default()).await?;
+    let mut readings = [None; K];
+    for (i, reading) in readings.iter_mut().enumerate() {
+        let code: O::DacValue =
+            O::DacValue::try_from(FloatCore::round(max * (i as f64 + 0.5) / K as f64).as_())
+                .unwrap_or_else(|_| panic!("should never happen"));
+        if config.observer.is_cancelled() {
+            return Err(TuneError::Cancelled {
+                phase: Phase::Main,
+                index: i,
+            });
+        }
+        set_main(o, code).await?;
+        *reading = match measure(o, config, None).await {
+            Ok(period) => Some((code.into().as_(), period.seconds())),
+            Err(TuneError::Timeout) => None,
+            Err(error) => return Err(error),
+        };
+    }
+
+    let mut points = [(0.0, 0.0); K];
+    let mut len = 0;
+    for &point in readings.iter().flatten() {
+        points[len] = point;
+        len += 1;
+    }
+    if len < 3 {
+        return Err(TuneError::Timeout);
+    }
+
+    let model = ExpModel::fit(&points[..len]);
+    let residuals = readings.map(|reading| {
+        reading.map(|(code, period)| (1200.0 * log2(period * model.frequency(code))) as f32)
+    });
+    Ok(ModelFit { model, residuals })
+}
+
+pub(crate) async fn apply<O: OscfExtPriv + ?Sized, const N: usize, A>(
+    o: &mut O,
+    model: &ExpModel,
+    main_table: &mut A,
+    offset_table: &mut A,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<O::DacValue, O::Period>,
+    >,
+) -> TuneResult<O, O::DacValue>
+where
+    A: TuningTable<N, DacValue = O::DacValue>,
+    <O::DacValue as Ux>::Rep: Add<Output = <O::DacValue as Ux>::Rep>
+        + Sub<Output = <O::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+        + AsPrimitive<f64>,
+    f64: AsPrimitive<<O::DacValue as Ux>::Rep>,
+{
+    let ratio = Settled::new(o, config.settling)
+        .find_ratio(None, config)
+        .await?;
+    let steps: f64 = ratio.into().as_();
+    let max: f64 = O::DacValue::MAX.into().as_();
+    let code = |value: f64| {
+        O::DacValue::try_from(value.as_()).unwrap_or_else(|_| panic!("should never happen"))
+    };
+
+    for i in 0..N {
+        let target: O::Period = note_period(main_table, i, config)?;
+        config.observer.event(TuneEvent::Anchor {
+            phase: Phase::Main,
+            index: i,
+            count: N,
+            target,
+        });
+        if config.observer.is_cancelled() {
+            return Err(TuneError::Cancelled {
+                phase: Phase::Main,
+                index: i,
+            });
+        }
+        let frequency = config.tuning.frequency(A::note(i) as f32) as f64;
+        let exact = model.code_for_frequency(frequency).unwrap_or(f64::INFINITY);
+        if exact < 0.0 {
+            return Err(TuneError::Unreachable {
+                target,
+                limit: Limit::Min,
+            });
+        }
+        if exact > max {
+            return Err(TuneError::Unreachable {
+                target,
+                limit: Limit::Max,
+            });
+        }
+        let main = FloatCore::floor(exact);
+        let offset = FloatCore::round((exact - main) * steps).min(max);
+        main_table.anchor(i).set(code(main));
+        offset_table.anchor(i).set(code(offset));
+    }
+    main_table.finish();
+    offset_table.finish();
+    Ok(ratio)
+}
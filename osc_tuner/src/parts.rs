@@ -0,0 +1,187 @@
+//! Hardware pieces an oscillator is built from, so one counter or DAC driver
+//! can be reused across voices and boards. [`Composed`] turns them into a
+//! [`TryOscf`].
+
+use core::{
+    future::Future,
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::One;
+use uxt::Ux;
+
+use crate::{
+    error::MeasureError,
+    period::{MicrosPeriod, Period},
+    OscfExt, TryOscf,
+};
+
+/// A frequency counter reading the period of whatever is routed to it.
+pub trait PeriodMeter {
+    type Period: Period = MicrosPeriod;
+    type Error;
+
+    fn measure_period(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Self::Period, MeasureError<Self::Error>>>;
+}
+
+/// One DAC channel.
+pub trait Dac {
+    type Value: Copy + 'static + Ux;
+    type Error;
+
+    fn write(&mut self, value: Self::Value) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Waits for the oscillator to follow a DAC write.
+pub trait Settle {
+    fn settle(&mut self) -> impl Future<Output = ()>;
+}
+
+/// Measures right after every write.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NoSettle;
+
+impl Settle for NoSettle {
+    async fn settle(&mut self) {}
+}
+
+impl<F: FnMut() -> G, G: Future<Output = ()>> Settle for F {
+    async fn settle(&mut self) {
+        self().await
+    }
+}
+
+impl<M: PeriodMeter + ?Sized> PeriodMeter for &mut M {
+    type Period = M::Period;
+    type Error = M::Error;
+
+    fn measure_period(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Self::Period, MeasureError<Self::Error>>> {
+        (**self).measure_period(timeout)
+    }
+}
+
+impl<D: Dac + ?Sized> Dac for &mut D {
+    type Value = D::Value;
+    type Error = D::Error;
+
+    fn write(&mut self, value: Self::Value) -> impl Future<Output = Result<(), Self::Error>> {
+        (**self).write(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposedError<M, D, O> {
+    Meter(M),
+    Main(D),
+    Offset(O),
+}
+
+/// An oscillator made of a period meter, a main DAC, an optional offset DAC
+/// and a settling delay. Writes to a missing offset DAC are dropped. The
+/// delay runs once before the first reading after any writes, so a main and
+/// offset pair settles once. A tune runs it through
+/// [`TryOscf::settle_writes`], so a meter shared between voices is never held
+/// while one of them settles.
+#[derive(Debug)]
+pub struct Composed<M, D, O = D, S = NoSettle> {
+    pub meter: M,
+    pub main: D,
+    pub offset: Option<O>,
+    pub settle: S,
+    unsettled: bool,
+}
+
+impl<M, D, O> Composed<M, D, O> {
+    pub fn new(meter: M, main: D, offset: Option<O>) -> Self {
+        Self {
+            meter,
+            main,
+            offset,
+            settle: NoSettle,
+            unsettled: false,
+        }
+    }
+}
+
+impl<M, D, O, S> Composed<M, D, O, S> {
+    pub fn with_settle<T: Settle>(self, settle: T) -> Composed<M, D, O, T> {
+        Composed {
+            meter: self.meter,
+            main: self.main,
+            offset: self.offset,
+            settle,
+            unsettled: self.unsettled,
+        }
+    }
+}
+
+impl<M, D, O, S> TryOscf for Composed<M, D, O, S>
+where
+    M: PeriodMeter,
+    D: Dac,
+    O: Dac<Value = D::Value>,
+    S: Settle,
+{
+    type DacValue = D::Value;
+    type Period = M::Period;
+    type Error = ComposedError<M::Error, D::Error, O::Error>;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        self.settle_writes().await;
+        self.meter
+            .measure_period(timeout)
+            .await
+            .map_err(|error| match error {
+                MeasureError::NoEdges => MeasureError::NoEdges,
+                MeasureError::Timeout => MeasureError::Timeout,
+                MeasureError::Unsettled => MeasureError::Unsettled,
+                MeasureError::Device(error) => MeasureError::Device(ComposedError::Meter(error)),
+            })
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.unsettled = true;
+        self.main.write(value).await.map_err(ComposedError::Main)
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        let Some(offset) = &mut self.offset else {
+            return Ok(());
+        };
+        self.unsettled = true;
+        offset.write(value).await.map_err(ComposedError::Offset)
+    }
+
+    async fn settle_writes(&mut self) {
+        if self.unsettled {
+            self.settle.settle().await;
+            self.unsettled = false;
+        }
+    }
+}
+
+impl<M, D, O, S> OscfExt for Composed<M, D, O, S>
+where
+    M: PeriodMeter,
+    D: Dac,
+    O: Dac<Value = D::Value>,
+    S: Settle,
+    <D::Value as Ux>::Rep: Add<Output = <D::Value as Ux>::Rep>
+        + Sub<Output = <D::Value as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy,
+{
+}
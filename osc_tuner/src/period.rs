@@ -0,0 +1,80 @@
+use core::{fmt::Debug, num::NonZeroU32};
+
+/// A period reading in counter ticks.
+///
+/// Implementors carry the unit in the type so that tuning targets are
+/// computed in the same unit at compile time.
+pub trait Period: Copy + Ord + Debug + 'static {
+    /// Counter ticks per second.
+    const TICK_HZ: u32;
+    /// Oscillator cycles covered by a single reading.
+    const CYCLES: u32;
+
+    fn from_ticks(ticks: u32) -> Option<Self>;
+    fn ticks(self) -> u32;
+
+    /// Length of one oscillator cycle in seconds.
+    fn seconds(self) -> f64 {
+        self.ticks() as f64 / (Self::TICK_HZ as f64 * Self::CYCLES as f64)
+    }
+
+    fn from_seconds(seconds: f64) -> Option<Self> {
+        let ticks = seconds * Self::TICK_HZ as f64 * Self::CYCLES as f64;
+        if !(ticks >= 0.5 && ticks < u32::MAX as f64) {
+            return None;
+        }
+        Self::from_ticks((ticks + 0.5) as u32)
+    }
+
+    fn from_frequency(hz: f64) -> Option<Self> {
+        Self::from_seconds(1.0 / hz)
+    }
+}
+
+/// Ticks of a `HZ` clock counted over `CYCLES` oscillator cycles, e.g.
+/// `Ticks<72_000_000>` for a 72 MHz input capture or `Ticks<72_000_000, 16>`
+/// for a reciprocal counter gated over 16 cycles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Ticks<const HZ: u32, const CYCLES: u32 = 1>(NonZeroU32);
+
+pub type MicrosPeriod = Ticks<1_000_000>;
+
+impl<const HZ: u32, const CYCLES: u32> Ticks<HZ, CYCLES> {
+    pub const fn new(ticks: u32) -> Option<Self> {
+        match NonZeroU32::new(ticks) {
+            Some(ticks) => Some(Self(ticks)),
+            None => None,
+        }
+    }
+
+    pub const fn get(self) -> u32 {
+        self.0.get()
+    }
+
+    /// Returns `None` when the period does not fit the counter.
+    pub const fn from_frequency(hz: f64) -> Option<Self> {
+        let ticks = HZ as f64 * CYCLES as f64 / hz;
+        if !(ticks >= 0.5 && ticks < u32::MAX as f64) {
+            return None;
+        }
+        Self::new((ticks + 0.5) as u32)
+    }
+}
+
+impl<const HZ: u32, const CYCLES: u32> Period for Ticks<HZ, CYCLES> {
+    const TICK_HZ: u32 = HZ;
+    const CYCLES: u32 = CYCLES;
+
+    fn from_ticks(ticks: u32) -> Option<Self> {
+        Self::new(ticks)
+    }
+
+    fn from_frequency(hz: f64) -> Option<Self> {
+        Ticks::from_frequency(hz)
+    }
+
+    fn ticks(self) -> u32 {
+        self.get()
+    }
+}
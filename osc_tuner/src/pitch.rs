@@ -0,0 +1,91 @@
+use num_traits::{float::FloatCore, AsPrimitive};
+use uxt::Ux;
+
+use crate::{
+    domain::MidiOscDomain,
+    table::{dac_from_position, NoteCurve, Table},
+    tuning::{Edo, TuningSystem},
+};
+
+/// Turns notes into `(main_dac, offset_dac)` pairs using the tables and the
+/// ratio produced by `OscfExt::tune_midi_frequencies`. Works with any table
+/// layout, the compact [`Table`] by default.
+///
+/// `ratio` is the number of offset DAC steps that make up one main DAC step,
+/// so the fractional part of the interpolated main code is carried by the
+/// offset DAC on top of the per-anchor offset correction. Tuned anchors come
+/// back exactly as stored.
+///
+/// Notes between anchors are placed by the pitch `tuning` gives them, which
+/// must be the tuning the tables were tuned to. The default suits 12-TET.
+pub struct PitchEngine<'a, T: Ux, C = Table<T>, S = Edo> {
+    main: &'a C,
+    offset: &'a C,
+    ratio: T,
+    tuning: S,
+}
+
+impl<'a, T: Ux + Copy, C: NoteCurve<DacValue = T>> PitchEngine<'a, T, C>
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    pub fn new(main: &'a C, offset: &'a C, ratio: T) -> Self {
+        Self {
+            main,
+            offset,
+            ratio,
+            tuning: Edo::default(),
+        }
+    }
+}
+
+impl<'a, T: Ux + Copy, C: NoteCurve<DacValue = T>, S: TuningSystem> PitchEngine<'a, T, C, S>
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    pub fn with_tuning<U: TuningSystem>(self, tuning: U) -> PitchEngine<'a, T, C, U> {
+        PitchEngine {
+            main: self.main,
+            offset: self.offset,
+            ratio: self.ratio,
+            tuning,
+        }
+    }
+
+    /// `cents` carries pitch bend and fine tune. The result is clamped to the
+    /// oscillator domain.
+    pub fn dac_pair(&self, note: MidiOscDomain, cents: f32) -> (T, T) {
+        let (main, offset, ratio) = self.positions(note, cents);
+        split(main, offset, ratio)
+    }
+
+    /// Fractional main and offset codes for `note` and the ratio as a float,
+    /// before the main code is split across both DACs.
+    pub(crate) fn positions(&self, note: MidiOscDomain, cents: f32) -> (f32, f32, f32) {
+        let note = MidiOscDomain::clamp(note.get() + cents / 100.0);
+        (
+            self.main.position_for_note_in(note, &self.tuning),
+            self.offset.position_for_note_in(note, &self.tuning),
+            self.ratio.into().as_(),
+        )
+    }
+}
+
+/// Moves the fractional part of `main` onto the offset DAC.
+pub(crate) fn split<T: Ux + Copy>(main: f32, offset: f32, ratio: f32) -> (T, T)
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    if ratio <= 0.0 {
+        return (dac_from_position(main), dac_from_position(offset));
+    }
+
+    let coarse = FloatCore::floor(main);
+    (
+        dac_from_position(coarse),
+        dac_from_position(offset + (main - coarse) * ratio),
+    )
+}
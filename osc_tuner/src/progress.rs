@@ -0,0 +1,88 @@
+//! Progress reporting and cancellation of a running tune.
+
+use core::cell::Cell;
+
+/// Step of [`crate::OscfExt::tune_midi_frequencies`], in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Main,
+    Offset,
+    Ratio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuneEvent<D, P> {
+    /// The search for anchor `index` of `count` in `phase` starts. The ratio
+    /// is a single anchor.
+    Anchor {
+        phase: Phase,
+        index: usize,
+        count: usize,
+        target: P,
+    },
+    /// Step `iteration` of the current search, bisecting or interpolating,
+    /// narrowed the codes holding the target down to `low..=high`.
+    Bracket { iteration: usize, low: D, high: D },
+}
+
+/// Sees every [`TuneEvent`] of a tune and may cancel it. A cancelled tune
+/// stops before its next reading with [`crate::error::TuneError::Cancelled`].
+pub trait TuneObserver<D, P> {
+    fn event(&self, _event: TuneEvent<D, P>) {}
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NoObserver;
+
+impl<D, P> TuneObserver<D, P> for NoObserver {}
+
+/// Cancels a tune from outside, e.g. from a front panel button.
+#[derive(Debug, Default, PartialEq)]
+pub struct CancelToken(Cell<bool>);
+
+impl CancelToken {
+    pub const fn new() -> Self {
+        Self(Cell::new(false))
+    }
+
+    pub fn cancel(&self) {
+        self.0.set(true);
+    }
+
+    /// Makes the token usable for another tune.
+    pub fn reset(&self) {
+        self.0.set(false);
+    }
+}
+
+impl<D, P> TuneObserver<D, P> for CancelToken {
+    fn is_cancelled(&self) -> bool {
+        self.0.get()
+    }
+}
+
+impl<D, P, T: TuneObserver<D, P> + ?Sized> TuneObserver<D, P> for &T {
+    fn event(&self, event: TuneEvent<D, P>) {
+        (**self).event(event)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+/// Both observers see every event; either may cancel.
+impl<D: Copy, P: Copy, A: TuneObserver<D, P>, B: TuneObserver<D, P>> TuneObserver<D, P> for (A, B) {
+    fn event(&self, event: TuneEvent<D, P>) {
+        self.0.event(event);
+        self.1.event(event);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled() || self.1.is_cancelled()
+    }
+}
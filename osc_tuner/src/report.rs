@@ -0,0 +1,186 @@
+use core::time::Duration;
+
+use num_traits::float::FloatCore;
+
+use crate::{
+    config::TuneConfig,
+    error::MeasureError,
+    key_frequencies::log2,
+    measurement::MeasurementStrategy,
+    note_period,
+    period::Period,
+    progress::TuneObserver,
+    set_main, set_offset,
+    settling::Settled,
+    table::{TuningTable, TABLE_ANCHORS},
+    tuning::TuningSystem,
+    TryOscf, TuneResult,
+};
+
+/// Verification result for a single tuned anchor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteReport<P> {
+    /// MIDI note of the anchor.
+    pub note: u8,
+    pub target: P,
+    pub measured: P,
+    /// Pitch error of `measured` against `target`, positive when sharp.
+    pub cents: f32,
+    /// Raw period readings taken for this anchor.
+    pub measurements: usize,
+    pub pass: bool,
+}
+
+/// Re-measured anchors of a tuned table pair, in [`TuningTable::anchor`] order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuningReport<P, const N: usize = TABLE_ANCHORS> {
+    /// Main table codes played with the offset DAC at zero. These are only
+    /// as fine as one main step.
+    pub main: [NoteReport<P>; N],
+    /// Main and offset table codes played together, i.e. what the
+    /// instrument actually plays.
+    pub offset: [NoteReport<P>; N],
+}
+
+impl<P, const N: usize> TuningReport<P, N> {
+    /// Whether every note played through both tables is within the threshold.
+    pub fn passed(&self) -> bool {
+        self.offset.iter().all(|note| note.pass)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &NoteReport<P>> {
+        self.offset.iter().filter(|note| !note.pass)
+    }
+
+    /// Largest absolute error of the notes played through both tables.
+    pub fn max_error(&self) -> f32 {
+        self.offset
+            .iter()
+            .map(|note| FloatCore::abs(note.cents))
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Pitch error in cents of `measured` against `target`, positive when sharp.
+pub fn cents<P: Period>(measured: P, target: P) -> f32 {
+    (1200.0 * log2(target.seconds() / measured.seconds())) as f32
+}
+
+pub(crate) async fn verify<
+    O: TryOscf + ?Sized,
+    const N: usize,
+    A: TuningTable<N, DacValue = O::DacValue>,
+>(
+    o: &mut O,
+    main_table: &A,
+    offset_table: &A,
+    threshold: f32,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<O::DacValue, O::Period>,
+    >,
+) -> TuneResult<O, TuningReport<O::Period, N>> {
+    let o = &mut Settled::new(o, config.settling);
+    let zero = Default::default();
+    let mut main = [None; N];
+    let mut offset = [None; N];
+
+    for i in 0..N {
+        let dac = main_table.anchor(i).get();
+        let target = note_period(main_table, i, config)?;
+        let note = A::note(i) as u8;
+        main[i] = Some(verify_note(o, note, target, dac, zero, threshold, config).await?);
+        offset[i] = Some(
+            verify_note(
+                o,
+                note,
+                target,
+                dac,
+                offset_table.anchor(i).get(),
+                threshold,
+                config,
+            )
+            .await?,
+        );
+    }
+
+    let unwrap = |note: Option<_>| note.unwrap_or_else(|| panic!("should never happen"));
+    Ok(TuningReport {
+        main: main.map(unwrap),
+        offset: offset.map(unwrap),
+    })
+}
+
+async fn verify_note<O: TryOscf + ?Sized>(
+    o: &mut O,
+    note: u8,
+    target: O::Period,
+    main: O::DacValue,
+    offset: O::DacValue,
+    threshold: f32,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<O::DacValue, O::Period>,
+    >,
+) -> TuneResult<O, NoteReport<O::Period>> {
+    set_main(o, main).await?;
+    set_offset(o, offset).await?;
+
+    let mut counted = Counted { o, count: 0 };
+    let measured = config
+        .measurement
+        .measure(&mut counted, config.timeout, None)
+        .await?;
+
+    let cents = cents(measured, target);
+    Ok(NoteReport {
+        note,
+        target,
+        measured,
+        cents,
+        measurements: counted.count,
+        pass: FloatCore::abs(cents) <= threshold,
+    })
+}
+
+// Counts the raw readings a measurement strategy takes.
+struct Counted<'a, O: ?Sized> {
+    o: &'a mut O,
+    count: usize,
+}
+
+impl<'a, O: TryOscf + ?Sized> TryOscf for Counted<'a, O> {
+    type DacValue = O::DacValue;
+    type Period = O::Period;
+    type Error = O::Error;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        self.count += 1;
+        self.o.try_get_period(timeout).await
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.o.try_set_main_dac(value).await
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.o.try_set_offset_dac(value).await
+    }
+
+    async fn settle_writes(&mut self) {
+        self.o.settle_writes().await
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        self.o.try_delay(duration, timeout).await
+    }
+}
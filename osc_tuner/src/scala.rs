@@ -0,0 +1,327 @@
+//! Scala scale (`.scl`) and keyboard mapping (`.kbm`) files, parsed from byte
+//! slices without allocation.
+
+use num_traits::float::FloatCore;
+
+use crate::{
+    key_frequencies::{exp2, log2},
+    tuning::{Edo, TuningSystem},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScalaError {
+    /// 1-based line the error was found on.
+    pub line: usize,
+    pub kind: ScalaErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalaErrorKind {
+    InvalidUtf8,
+    /// The file ended before all required lines were read.
+    UnexpectedEnd,
+    InvalidNumber,
+    /// A pitch is neither cents nor a positive ratio.
+    InvalidPitch,
+    /// The scale has no notes.
+    EmptyScale,
+    TooManyNotes {
+        capacity: usize,
+    },
+    TooManyKeys {
+        capacity: usize,
+    },
+    /// A MIDI key outside 0..=127.
+    KeyOutOfRange,
+    /// A mapping entry is neither a scale degree nor `x`.
+    InvalidDegree,
+    /// The reference key is left unmapped.
+    UnmappedReference,
+}
+
+/// Pitches of a `.scl` file in cents above 1/1. The last one is the period,
+/// usually 2/1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale<const N: usize = 128> {
+    len: usize,
+    cents: [f64; N],
+}
+
+impl<const N: usize> Scale<N> {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ScalaError> {
+        let mut lines = Lines::new(bytes);
+
+        // The description may be empty, so only comments are skipped here.
+        lines.next_raw()?;
+
+        let (line, len) = lines.next_number::<usize>()?;
+        if len == 0 {
+            return Err(error(line, ScalaErrorKind::EmptyScale));
+        }
+        if len > N {
+            return Err(error(line, ScalaErrorKind::TooManyNotes { capacity: N }));
+        }
+
+        let mut cents = [0.0; N];
+        for cents in cents[..len].iter_mut() {
+            let (line, pitch) = lines.next_value()?;
+            *cents =
+                parse_pitch(first_token(pitch)).ok_or(error(line, ScalaErrorKind::InvalidPitch))?;
+        }
+        Ok(Self { len, cents })
+    }
+
+    /// Number of notes, including the period but not 1/1.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Cents of scale degree `degree`, which may lie outside the first period.
+    pub fn cents(&self, degree: i32) -> f64 {
+        let len = self.len as i32;
+        let period = degree.div_euclid(len);
+        let step = degree.rem_euclid(len) as usize;
+        let base = if step == 0 { 0.0 } else { self.cents[step - 1] };
+        period as f64 * self.cents[self.len - 1] + base
+    }
+}
+
+/// Key to scale degree assignment of a `.kbm` file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyboardMap<const M: usize = 128> {
+    size: usize,
+    pub first: u8,
+    pub last: u8,
+    /// Key that plays scale degree 0.
+    pub middle: u8,
+    pub reference: u8,
+    /// Frequency in Hz of `reference`.
+    pub frequency: f64,
+    /// Scale degree one repetition of the mapping spans.
+    pub octave_degree: i32,
+    mapping: [Option<i32>; M],
+}
+
+impl<const M: usize> KeyboardMap<M> {
+    /// Successive keys play successive degrees, with `middle` on 1/1.
+    pub fn linear(middle: u8, reference: u8, frequency: f64) -> Self {
+        Self {
+            size: 0,
+            first: 0,
+            last: 127,
+            middle,
+            reference,
+            frequency,
+            octave_degree: 0,
+            mapping: [None; M],
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, ScalaError> {
+        let mut lines = Lines::new(bytes);
+
+        let (line, size) = lines.next_number::<usize>()?;
+        if size > M {
+            return Err(error(line, ScalaErrorKind::TooManyKeys { capacity: M }));
+        }
+
+        let first = lines.next_key()?.1;
+        let last = lines.next_key()?.1;
+        let middle = lines.next_key()?.1;
+        let (reference_line, reference) = lines.next_key()?;
+
+        let (line, frequency) = lines.next_number::<f64>()?;
+        if !(frequency > 0.0 && frequency.is_finite()) {
+            return Err(error(line, ScalaErrorKind::InvalidNumber));
+        }
+
+        let octave_degree = lines.next_number()?.1;
+
+        // Missing trailing entries leave their keys unmapped.
+        let mut mapping = [None; M];
+        for entry in mapping[..size].iter_mut() {
+            let Some((line, value)) = lines.try_next_value()? else {
+                break;
+            };
+            *entry = match first_token(value) {
+                "x" | "X" => None,
+                value => Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(line, ScalaErrorKind::InvalidDegree))?,
+                ),
+            };
+        }
+
+        let map = Self {
+            size,
+            first,
+            last,
+            middle,
+            reference,
+            frequency,
+            octave_degree,
+            mapping,
+        };
+        if map.degree(reference).is_none() {
+            return Err(error(reference_line, ScalaErrorKind::UnmappedReference));
+        }
+        Ok(map)
+    }
+
+    /// Scale degree `key` plays, `None` for keys the map leaves alone.
+    pub fn degree(&self, key: u8) -> Option<i32> {
+        if key < self.first || key > self.last {
+            return None;
+        }
+        let offset = key as i32 - self.middle as i32;
+        if self.size == 0 {
+            return Some(offset);
+        }
+        let size = self.size as i32;
+        let repeat = offset.div_euclid(size);
+        let degree = self.mapping[offset.rem_euclid(size) as usize]?;
+        Some(repeat * self.octave_degree + degree)
+    }
+}
+
+/// A Scala scale played through a keyboard map. Keys the map leaves alone
+/// keep 12-TET at 440 Hz, fractional notes are interpolated in cents between
+/// the neighbouring keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalaTuning<const N: usize = 128, const M: usize = 128> {
+    pub scale: Scale<N>,
+    pub map: KeyboardMap<M>,
+}
+
+impl<const N: usize, const M: usize> ScalaTuning<N, M> {
+    pub fn new(scale: Scale<N>, map: KeyboardMap<M>) -> Self {
+        Self { scale, map }
+    }
+
+    /// Parses a `.scl` file played through a `.kbm` file.
+    pub fn parse(scl: &[u8], kbm: &[u8]) -> Result<Self, ScalaError> {
+        Ok(Self::new(Scale::parse(scl)?, KeyboardMap::parse(kbm)?))
+    }
+
+    /// Frequency in Hz of MIDI `key`, `None` when the map leaves it alone.
+    pub fn key_frequency(&self, key: u8) -> Option<f64> {
+        let degree = self.map.degree(key)?;
+        let reference = self.map.degree(self.map.reference)?;
+        let cents = self.scale.cents(degree) - self.scale.cents(reference);
+        Some(self.map.frequency * exp2(cents / 1200.0))
+    }
+
+    fn key_frequency_or_default(&self, key: u8) -> f64 {
+        self.key_frequency(key)
+            .unwrap_or_else(|| Edo::default().frequency(key as f32) as f64)
+    }
+}
+
+impl<const N: usize, const M: usize> TuningSystem for ScalaTuning<N, M> {
+    fn frequency(&self, note: f32) -> f32 {
+        let note = note.clamp(0.0, 127.0);
+        let key = FloatCore::floor(note);
+        let low = self.key_frequency_or_default(key as u8);
+        if key == note {
+            return low as f32;
+        }
+        let high = self.key_frequency_or_default(key as u8 + 1);
+        (low * exp2((note - key) as f64 * log2(high / low))) as f32
+    }
+}
+
+fn error(line: usize, kind: ScalaErrorKind) -> ScalaError {
+    ScalaError { line, kind }
+}
+
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+// Cents contain a period, anything else is a ratio or a whole number.
+fn parse_pitch(token: &str) -> Option<f64> {
+    if token.contains('.') {
+        return token.parse().ok().filter(|cents: &f64| cents.is_finite());
+    }
+    let (num, den) = token.split_once('/').unwrap_or((token, "1"));
+    let num: u64 = num.parse().ok()?;
+    let den: u64 = den.parse().ok()?;
+    if num == 0 || den == 0 {
+        return None;
+    }
+    Some(1200.0 * log2(num as f64 / den as f64))
+}
+
+// Lines with `!` comments skipped, numbered from 1.
+struct Lines<'a> {
+    bytes: &'a [u8],
+    line: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, line: 0 }
+    }
+
+    // Next non-comment line, blank or not.
+    fn next_raw(&mut self) -> Result<(usize, &'a str), ScalaError> {
+        loop {
+            if self.bytes.is_empty() {
+                return Err(error(self.line + 1, ScalaErrorKind::UnexpectedEnd));
+            }
+            let end = self
+                .bytes
+                .iter()
+                .position(|&b| b == b'\n')
+                .unwrap_or(self.bytes.len());
+            let raw = &self.bytes[..end];
+            self.bytes = self.bytes.get(end + 1..).unwrap_or(&[]);
+            self.line += 1;
+
+            let text = core::str::from_utf8(raw)
+                .map_err(|_| error(self.line, ScalaErrorKind::InvalidUtf8))?
+                .trim_end_matches('\r');
+            if !text.starts_with('!') {
+                return Ok((self.line, text));
+            }
+        }
+    }
+
+    fn try_next_value(&mut self) -> Result<Option<(usize, &'a str)>, ScalaError> {
+        while !self.bytes.is_empty() {
+            let (line, text) = self.next_raw()?;
+            if !text.trim().is_empty() {
+                return Ok(Some((line, text)));
+            }
+        }
+        Ok(None)
+    }
+
+    // Next non-blank, non-comment line.
+    fn next_value(&mut self) -> Result<(usize, &'a str), ScalaError> {
+        match self.try_next_value()? {
+            Some(value) => Ok(value),
+            None => Err(error(self.line + 1, ScalaErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn next_number<T: core::str::FromStr>(&mut self) -> Result<(usize, T), ScalaError> {
+        let (line, value) = self.next_value()?;
+        let number = first_token(value)
+            .parse()
+            .map_err(|_| error(line, ScalaErrorKind::InvalidNumber))?;
+        Ok((line, number))
+    }
+
+    fn next_key(&mut self) -> Result<(usize, u8), ScalaError> {
+        match self.next_number::<i64>()? {
+            (line, key @ 0..=127) => Ok((line, key as u8)),
+            (line, _) => Err(error(line, ScalaErrorKind::KeyOutOfRange)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! How a search picks the next code to measure inside its bracket.
+
+use core::ops::{Add, Sub};
+
+use num_integer::Average;
+use num_traits::One;
+use uxt::Ux;
+
+use crate::{key_frequencies::log2, period::Period};
+
+/// Both strategies keep the same bracket invariants, so on a monotonic
+/// oscillator they settle on the same code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStrategy {
+    /// Halves the bracket with every reading.
+    #[default]
+    Bisection,
+    /// Models log(period) as linear in the DAC code, which a V/oct oscillator
+    /// nearly is, and measures where the model puts the target. Falls back to
+    /// bisection whenever the same end of the bracket moved twice in a row.
+    Interpolation,
+}
+
+/// Which end of the bracket the previous reading replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    Low,
+    High,
+}
+
+/// Next code to measure strictly between `low` and `high`, which must be at
+/// least two codes apart.
+pub(crate) fn next_code<D: Ux + Copy, P: Period>(
+    strategy: SearchStrategy,
+    (low, low_period): (D, P),
+    (high, high_period): (D, P),
+    target: P,
+    moved: [Option<Side>; 2],
+) -> D
+where
+    D::Rep: Add<Output = D::Rep> + Sub<Output = D::Rep> + One + PartialOrd + Average + Copy,
+{
+    let try_from = |x| D::try_from(x).unwrap_or_else(|_| panic!("should never happen"));
+    let one = D::Rep::one();
+    let (low, high) = (low.into(), high.into());
+    let midpoint = try_from(low.average_floor(&high));
+
+    if strategy == SearchStrategy::Bisection || (moved[0].is_some() && moved[0] == moved[1]) {
+        return midpoint;
+    }
+    let span = log2(low_period.ticks() as f64 / high_period.ticks() as f64);
+    if span <= 0.0 {
+        return midpoint;
+    }
+    let fraction = log2(low_period.ticks() as f64 / target.ticks() as f64) / span;
+
+    // Walks down the bisection tree towards `fraction`, which needs nothing
+    // but halving from the code type.
+    let (mut lo, mut hi, mut fraction) = (low, high, fraction.clamp(0.0, 1.0));
+    while hi - lo > one {
+        let mid = lo.average_floor(&hi);
+        if fraction >= 0.5 {
+            lo = mid;
+            fraction = fraction * 2.0 - 1.0;
+        } else {
+            hi = mid;
+            fraction *= 2.0;
+        }
+    }
+    let code = if fraction >= 0.5 { hi } else { lo };
+
+    if code <= low {
+        try_from(low + one)
+    } else if code >= high {
+        try_from(high - one)
+    } else {
+        try_from(code)
+    }
+}
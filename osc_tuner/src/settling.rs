@@ -0,0 +1,138 @@
+//! What happens between a DAC write and the reading that uses it. An
+//! oscillator whose delay is part of the hardware, e.g.
+//! [`crate::parts::Composed::with_settle`], keeps a shared counter free for
+//! other voices while it waits; a delay set here goes through
+//! [`TryOscf::try_delay`] instead.
+
+use core::{
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::{float::FloatCore, One};
+use uxt::Ux;
+
+use crate::{error::MeasureError, period::Period, OscfExt, TryOscf};
+
+/// Applied once between the DAC writes of every phase and the readings the
+/// measurement strategy takes after them.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Settling {
+    /// Periods read and dropped right after the write, for cores whose first
+    /// cycles after a change are wrong.
+    pub discard: u32,
+    pub delay: SettleDelay,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SettleDelay {
+    #[default]
+    None,
+    /// Waits this long through [`TryOscf::try_delay`].
+    Fixed(Duration),
+    /// Reads until two consecutive periods agree within `tolerance`
+    /// (relative), failing with [`MeasureError::Unsettled`] after
+    /// `max_readings`.
+    Adaptive { tolerance: f32, max_readings: u32 },
+}
+
+pub(crate) async fn settle<O: TryOscf + ?Sized>(
+    o: &mut O,
+    settling: &Settling,
+    timeout: Duration,
+) -> Result<(), MeasureError<O::Error>> {
+    for _ in 0..settling.discard {
+        o.try_get_period(timeout).await?;
+    }
+    match settling.delay {
+        SettleDelay::None => {}
+        SettleDelay::Fixed(duration) => o.try_delay(duration, timeout).await?,
+        SettleDelay::Adaptive {
+            tolerance,
+            max_readings,
+        } => {
+            let mut last: Option<O::Period> = None;
+            for _ in 0..max_readings {
+                let period = o.try_get_period(timeout).await?;
+                if last.is_some_and(|last| {
+                    FloatCore::abs(period.ticks() as f32 - last.ticks() as f32)
+                        <= last.ticks() as f32 * tolerance
+                }) {
+                    return Ok(());
+                }
+                last = Some(period);
+            }
+            return Err(MeasureError::Unsettled);
+        }
+    }
+    Ok(())
+}
+
+// Settles before the first reading after any number of DAC writes, so the
+// main and offset writes of one anchor settle once.
+pub(crate) struct Settled<'a, O: ?Sized> {
+    o: &'a mut O,
+    settling: Settling,
+    dirty: bool,
+}
+
+impl<'a, O: ?Sized> Settled<'a, O> {
+    pub(crate) fn new(o: &'a mut O, settling: Settling) -> Self {
+        Self {
+            o,
+            settling,
+            dirty: false,
+        }
+    }
+}
+
+impl<'a, O: TryOscf + ?Sized> TryOscf for Settled<'a, O> {
+    type DacValue = O::DacValue;
+    type Period = O::Period;
+    type Error = O::Error;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        if self.dirty {
+            self.o.settle_writes().await;
+            settle(self.o, &self.settling, timeout).await?;
+            self.dirty = false;
+        }
+        self.o.try_get_period(timeout).await
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.dirty = true;
+        self.o.try_set_main_dac(value).await
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.dirty = true;
+        self.o.try_set_offset_dac(value).await
+    }
+
+    async fn settle_writes(&mut self) {
+        self.o.settle_writes().await
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        self.o.try_delay(duration, timeout).await
+    }
+}
+
+impl<'a, O: TryOscf + ?Sized> OscfExt for Settled<'a, O> where
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
@@ -0,0 +1,284 @@
+use core::{
+    convert::Infallible,
+    future::Future,
+    marker::PhantomData,
+    ops::{Add, Sub},
+    pin::pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use num_integer::Average;
+use num_traits::{AsPrimitive, One};
+use uxt::Ux;
+
+use crate::{
+    error::MeasureError,
+    filter::{TryVcf, VcfExt},
+    period::{MicrosPeriod, Period},
+    OscfExt, TryOscf,
+};
+
+/// Parameters of a simulated exponential VCO driven by a main and an offset DAC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    /// Frequency in Hz at main and offset code 0.
+    pub base_frequency: f64,
+    /// Nominal main DAC codes per octave.
+    pub codes_per_octave: f64,
+    /// Offset DAC codes per main DAC code.
+    pub offset_ratio: f64,
+    /// Relative error of the V/oct scale, `0.01` is 1% too wide.
+    pub scale_error: f64,
+    /// Constant pitch error in octaves.
+    pub offset_error: f64,
+    /// Peak of the bow-shaped integral non-linearity, in main LSBs.
+    pub inl: f64,
+    /// Peak per-code differential non-linearity, in main LSBs.
+    pub dnl: f64,
+    /// Reset time added to every period in seconds, flattening the top octaves.
+    pub reset_time: f64,
+    /// Pitch drift in cents per degree away from `reference_temperature`.
+    pub tempco: f64,
+    pub reference_temperature: f64,
+    /// Relative RMS jitter of a single period reading.
+    pub jitter: f64,
+    /// Probability that a reading misses an edge and reports twice the period.
+    pub glitch_rate: f64,
+    /// When set the oscillator produces no edges.
+    pub dead: bool,
+    /// Time constant in seconds of the exponential glide to a new period
+    /// after a DAC write.
+    pub settle_time: f64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            base_frequency: 10.0,
+            codes_per_octave: 400.0,
+            offset_ratio: 32.0,
+            scale_error: 0.0,
+            offset_error: 0.0,
+            inl: 0.0,
+            dnl: 0.0,
+            reset_time: 0.0,
+            tempco: 0.0,
+            reference_temperature: 25.0,
+            jitter: 0.0,
+            glitch_rate: 0.0,
+            dead: false,
+            settle_time: 0.0,
+        }
+    }
+}
+
+/// Simulated oscillator read out by a counter of period type `P`.
+pub struct SimulatedOscf<D, P = MicrosPeriod> {
+    pub config: SimConfig,
+    pub temperature: f64,
+    main: f64,
+    offset: f64,
+    // Period when the last DAC write happened and readout time since.
+    glide: Option<(f64, f64)>,
+    rng: u64,
+    measurements: usize,
+    phantom: PhantomData<(D, P)>,
+}
+
+impl<D: Ux + Copy, P> SimulatedOscf<D, P>
+where
+    D::Rep: AsPrimitive<f64>,
+{
+    pub fn new(config: SimConfig) -> Self {
+        Self {
+            temperature: config.reference_temperature,
+            config,
+            main: 0.0,
+            offset: 0.0,
+            glide: None,
+            rng: 0x9E37_79B9_7F4A_7C15,
+            measurements: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seed | 1;
+        self
+    }
+
+    /// Number of period readings taken so far.
+    pub fn measurements(&self) -> usize {
+        self.measurements
+    }
+
+    /// Lets the oscillator glide on for `time` without reading it.
+    pub fn wait(&mut self, time: Duration) {
+        if let Some((_, since)) = &mut self.glide {
+            *since += time.as_secs_f64();
+        }
+    }
+
+    /// Noise free oscillator frequency for a pair of DAC codes.
+    pub fn frequency(&self, main: D, offset: D) -> f64 {
+        self.frequency_at(main.into().as_(), offset.into().as_())
+    }
+
+    fn frequency_at(&self, main: f64, offset: f64) -> f64 {
+        let c = &self.config;
+        let full_scale = D::MAX.into().as_().max(1.0);
+        let x = main / full_scale;
+        let inl = c.inl * 4.0 * x * (1.0 - x);
+        let dnl = c.dnl * (hash(main as u64) * 2.0 - 1.0);
+        let code = main + offset / c.offset_ratio + inl + dnl;
+        let drift = c.tempco * (self.temperature - c.reference_temperature) / 1200.0;
+        let octaves = code * (1.0 + c.scale_error) / c.codes_per_octave + c.offset_error + drift;
+        let ideal = c.base_frequency * octaves.exp2();
+        1.0 / (1.0 / ideal + c.reset_time)
+    }
+
+    fn gliding_period(&self) -> f64 {
+        let target = 1.0 / self.frequency_at(self.main, self.offset);
+        match self.glide {
+            // Where the glide is by the end of the cycle being read.
+            Some((from, since)) if self.config.settle_time > 0.0 => {
+                let t = since + target;
+                target + (from - target) * (-t / self.config.settle_time).exp()
+            }
+            _ => target,
+        }
+    }
+
+    fn start_glide(&mut self) {
+        self.glide = Some((self.gliding_period(), 0.0));
+    }
+
+    fn noise(&mut self) -> f64 {
+        // Irwin-Hall approximation of a unit normal.
+        (0..12).map(|_| self.next_unit()).sum::<f64>() - 6.0
+    }
+
+    fn next_unit(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<D: Ux + Copy + 'static, P: Period> TryOscf for SimulatedOscf<D, P>
+where
+    D::Rep: AsPrimitive<f64>,
+{
+    type DacValue = D;
+    type Period = P;
+    type Error = Infallible;
+
+    async fn try_get_period(&mut self, timeout: Duration) -> Result<P, MeasureError<Self::Error>> {
+        self.measurements += 1;
+        if self.config.dead {
+            return Err(MeasureError::NoEdges);
+        }
+
+        let period = self.gliding_period();
+        let mut period = period * (1.0 + self.config.jitter * self.noise());
+        if self.config.glitch_rate > 0.0 && self.next_unit() < self.config.glitch_rate {
+            period *= 2.0;
+        }
+        if let Some((_, since)) = &mut self.glide {
+            *since += period * P::CYCLES as f64;
+        }
+        if period * P::CYCLES as f64 > timeout.as_secs_f64() {
+            return Err(MeasureError::Timeout);
+        }
+
+        let ticks = period * P::TICK_HZ as f64 * P::CYCLES as f64;
+        let ticks = ticks.round().clamp(1.0, u32::MAX as f64);
+        Ok(P::from_ticks(ticks as u32).unwrap_or_else(|| panic!("should never happen")))
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.start_glide();
+        self.main = value.into().as_();
+        Ok(())
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.start_glide();
+        self.offset = value.into().as_();
+        Ok(())
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        _timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        self.wait(duration);
+        Ok(())
+    }
+}
+
+impl<D: Ux + Copy + 'static, P: Period> OscfExt for SimulatedOscf<D, P> where
+    D::Rep: AsPrimitive<f64>
+        + Add<Output = D::Rep>
+        + Sub<Output = D::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
+
+/// The main DAC doubles as the cutoff of a self-oscillating filter.
+impl<D: Ux + Copy + 'static, P: Period> TryVcf for SimulatedOscf<D, P>
+where
+    D::Rep: AsPrimitive<f64>,
+{
+    type DacValue = D;
+    type Period = P;
+    type Error = Infallible;
+
+    async fn try_get_period(&mut self, timeout: Duration) -> Result<P, MeasureError<Self::Error>> {
+        TryOscf::try_get_period(self, timeout).await
+    }
+
+    async fn try_set_cutoff_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.start_glide();
+        self.main = value.into().as_();
+        Ok(())
+    }
+}
+
+impl<D: Ux + Copy + 'static, P: Period> VcfExt for SimulatedOscf<D, P> where
+    D::Rep: AsPrimitive<f64>
+        + Add<Output = D::Rep>
+        + Sub<Output = D::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
+
+/// Polls `future` to completion on the current thread. The simulator never
+/// returns `Pending`, so this is all that is needed to drive it.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+// Stable per-code pseudo random value in [0, 1).
+fn hash(code: u64) -> f64 {
+    let mut x = code.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x ^= x >> 31;
+    x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x ^= x >> 29;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
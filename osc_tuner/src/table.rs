@@ -1,6 +1,11 @@
-use core::cell::Cell;
+use core::{array::from_fn, cell::Cell};
 
-use crate::domain::{C4, C9};
+use crate::{
+    domain::{MidiOscDomain, C0, C1, C4, C9},
+    key_frequencies::log2,
+    tuning::TuningSystem,
+};
+use num_traits::{float::FloatCore, AsPrimitive};
 use uxt::Ux;
 
 pub const C4_TO_C9: usize = C9 - C4 + 1;
@@ -10,3 +15,338 @@ pub struct Table<T: Ux> {
     pub c1: Cell<T>,
     pub c4_to_c9_with_filler: [Cell<T>; C4_TO_C9 + 1],
 }
+
+pub const TABLE_LEN: usize = C4_TO_C9 + 3;
+
+/// Tuned anchors of a [`Table`], i.e. every slot but the filler.
+pub const TABLE_ANCHORS: usize = C4_TO_C9 + 2;
+
+/// Fractional DAC codes interpolated between tuned anchors.
+pub trait NoteCurve {
+    type DacValue: Ux;
+
+    /// Interpolates as if every note were 12-TET, which suits tables tuned
+    /// to any equal division.
+    fn position_for_note(&self, note: MidiOscDomain) -> f32;
+
+    /// Notes of the anchors `position_for_note` interpolates between for
+    /// `note`, the outermost pair for notes beyond them.
+    fn segment(&self, note: f32) -> (usize, usize);
+
+    /// Like `position_for_note`, but places `note` between its anchors by
+    /// the pitch `tuning` gives it, for tables tuned to an unequal scale.
+    fn position_for_note_in(&self, note: MidiOscDomain, tuning: &impl TuningSystem) -> f32 {
+        let (low, high) = self.segment(note.get());
+        // Keeps anchors exact.
+        if note.get() == low as f32 || note.get() == high as f32 {
+            return self.position_for_note(note);
+        }
+        let log = |note: f32| log2(tuning.frequency(note) as f64);
+        let (log_low, log_high) = (log(low as f32), log(high as f32));
+        let t = (log(note.get()) - log_low) / (log_high - log_low);
+        let note = low as f32 + (high - low) as f32 * t as f32;
+        self.position_for_note(MidiOscDomain::clamp(note))
+    }
+}
+
+/// A table of DAC codes tuned at `N` fixed MIDI notes, filled by
+/// `OscfExt::tune_midi_frequencies`.
+pub trait TuningTable<const N: usize>: NoteCurve {
+    /// MIDI note anchor `index` is tuned to.
+    fn note(index: usize) -> usize;
+
+    fn anchor(&self, index: usize) -> &Cell<Self::DacValue>;
+
+    /// Called once every anchor has been tuned.
+    fn finish(&self) {}
+}
+
+impl<T: Ux> Table<T> {
+    pub fn new() -> Self {
+        Self {
+            c0: Cell::new(T::default()),
+            c1: Cell::new(T::default()),
+            c4_to_c9_with_filler: from_fn(|_| Cell::new(T::default())),
+        }
+    }
+
+    /// Iterates c0, c1 and the C4..C9 slots (including the filler) in storage order.
+    pub fn cells(&self) -> impl Iterator<Item = &Cell<T>> {
+        [&self.c0, &self.c1]
+            .into_iter()
+            .chain(self.c4_to_c9_with_filler.iter())
+    }
+}
+
+impl<T: Ux> Default for Table<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ux + Copy> Table<T>
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    /// Returns the DAC code that plays `note`.
+    ///
+    /// The DAC of a V/oct oscillator is linear in the note number, so anchors
+    /// are interpolated linearly in note space. The untuned C1..C4 gap is
+    /// bridged with a monotone cubic whose end slopes come from the C0..C1
+    /// segment and the first tuned octave above C4.
+    pub fn dac_for_note(&self, note: MidiOscDomain) -> T {
+        dac_from_position(self.position_for_note(note))
+    }
+
+    /// Returns the DAC code that plays `note` in the `tuning` the table was
+    /// tuned to.
+    pub fn dac_for_note_in(&self, note: MidiOscDomain, tuning: &impl TuningSystem) -> T {
+        dac_from_position(self.position_for_note_in(note, tuning))
+    }
+}
+
+impl<T: Ux + Copy> NoteCurve for Table<T>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    type DacValue = T;
+
+    fn position_for_note(&self, note: MidiOscDomain) -> f32 {
+        let note = note.get();
+        let at = |cell: &Cell<T>| -> f32 { cell.get().into().as_() };
+
+        let c0 = at(&self.c0);
+        let c1 = at(&self.c1);
+        let c4 = at(&self.c4_to_c9_with_filler[0]);
+
+        if note < C1 as f32 {
+            let t = (note - C0 as f32) / (C1 - C0) as f32;
+            return lerp(c0, c1, t);
+        }
+
+        if note < C4 as f32 {
+            let span = (C4 - C1) as f32;
+            let c5 = at(&self.c4_to_c9_with_filler[12]);
+            let (m1, m4) = monotone_slopes(
+                (c1 - c0) / (C1 - C0) as f32 * span,
+                (c5 - c4) / 12.0 * span,
+                c4 - c1,
+            );
+            return hermite(c1, c4, m1, m4, (note - C1 as f32) / span);
+        }
+
+        let offset = note - C4 as f32;
+        let i = (FloatCore::floor(offset) as usize).min(C4_TO_C9 - 1);
+        lerp(
+            at(&self.c4_to_c9_with_filler[i]),
+            at(&self.c4_to_c9_with_filler[i + 1]),
+            offset - i as f32,
+        )
+    }
+
+    fn segment(&self, note: f32) -> (usize, usize) {
+        if note < C1 as f32 {
+            (C0, C1)
+        } else if note < C4 as f32 {
+            (C1, C4)
+        } else {
+            let i = (FloatCore::floor(note - C4 as f32) as usize).min(C4_TO_C9 - 2);
+            (C4 + i, C4 + i + 1)
+        }
+    }
+}
+
+impl<T: Ux + Copy> TuningTable<TABLE_ANCHORS> for Table<T>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    fn note(index: usize) -> usize {
+        match index {
+            0 => C0,
+            1 => C1,
+            _ => C4 + index - 2,
+        }
+    }
+
+    fn anchor(&self, index: usize) -> &Cell<T> {
+        match index {
+            0 => &self.c0,
+            1 => &self.c1,
+            _ => &self.c4_to_c9_with_filler[index - 2],
+        }
+    }
+
+    fn finish(&self) {
+        self.c4_to_c9_with_filler[C4_TO_C9].set(self.c4_to_c9_with_filler[C4_TO_C9 - 1].get());
+    }
+}
+
+/// Table with `N` anchors tuned every `STEP` semitones starting at MIDI note
+/// `FIRST`, for oscillators whose tracking the compact [`Table`] layout
+/// cannot follow. Notes between anchors are interpolated linearly, notes
+/// outside extend the outermost segment.
+pub struct NoteTable<T: Ux, const FIRST: usize, const STEP: usize, const N: usize> {
+    pub anchors: [Cell<T>; N],
+}
+
+/// Every MIDI note.
+pub type FullTable<T> = NoteTable<T, 0, 1, 128>;
+
+impl<T: Ux, const FIRST: usize, const STEP: usize, const N: usize> NoteTable<T, FIRST, STEP, N> {
+    pub fn new() -> Self {
+        const { assert!(N >= 2 && STEP >= 1 && FIRST + (N - 1) * STEP <= 127) };
+        Self {
+            anchors: from_fn(|_| Cell::new(T::default())),
+        }
+    }
+}
+
+impl<T: Ux, const FIRST: usize, const STEP: usize, const N: usize> Default
+    for NoteTable<T, FIRST, STEP, N>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ux + Copy, const FIRST: usize, const STEP: usize, const N: usize>
+    NoteTable<T, FIRST, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    /// Returns the DAC code that plays `note`.
+    pub fn dac_for_note(&self, note: MidiOscDomain) -> T {
+        dac_from_position(self.position_for_note(note))
+    }
+
+    /// Returns the DAC code that plays `note` in the `tuning` the table was
+    /// tuned to.
+    pub fn dac_for_note_in(&self, note: MidiOscDomain, tuning: &impl TuningSystem) -> T {
+        dac_from_position(self.position_for_note_in(note, tuning))
+    }
+}
+
+impl<T: Ux + Copy, const FIRST: usize, const STEP: usize, const N: usize> NoteCurve
+    for NoteTable<T, FIRST, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    type DacValue = T;
+
+    fn position_for_note(&self, note: MidiOscDomain) -> f32 {
+        let at = |i: usize| -> f32 { self.anchors[i].get().into().as_() };
+        let x = (note.get() - FIRST as f32) / STEP as f32;
+        let i = (FloatCore::floor(x).max(0.0) as usize).min(N - 2);
+        lerp(at(i), at(i + 1), x - i as f32)
+    }
+
+    fn segment(&self, note: f32) -> (usize, usize) {
+        let x = (note - FIRST as f32) / STEP as f32;
+        let i = (FloatCore::floor(x).max(0.0) as usize).min(N - 2);
+        (FIRST + i * STEP, FIRST + (i + 1) * STEP)
+    }
+}
+
+impl<T: Ux + Copy, const FIRST: usize, const STEP: usize, const N: usize> TuningTable<N>
+    for NoteTable<T, FIRST, STEP, N>
+where
+    T::Rep: AsPrimitive<f32>,
+{
+    fn note(index: usize) -> usize {
+        FIRST + index * STEP
+    }
+
+    fn anchor(&self, index: usize) -> &Cell<T> {
+        &self.anchors[index]
+    }
+}
+
+pub(crate) fn dac_from_position<T: Ux + Copy>(position: f32) -> T
+where
+    T::Rep: AsPrimitive<f32>,
+    f32: AsPrimitive<T::Rep>,
+{
+    let max: f32 = T::MAX.into().as_();
+    let rep: T::Rep = FloatCore::round(position).clamp(0.0, max).as_();
+    T::try_from(rep).unwrap_or_else(|_| panic!("should never happen"))
+}
+
+pub(crate) fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Fritsch-Carlson limiting so the bridge never overshoots its endpoints.
+fn monotone_slopes(m0: f32, m1: f32, delta: f32) -> (f32, f32) {
+    if delta == 0.0 {
+        return (0.0, 0.0);
+    }
+    let alpha = (m0 / delta).clamp(0.0, 3.0);
+    let beta = (m1 / delta).clamp(0.0, 3.0);
+    (alpha * delta, beta * delta)
+}
+
+fn hermite(p0: f32, p1: f32, m0: f32, m1: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * p0
+        + (t3 - 2.0 * t2 + t) * m0
+        + (-2.0 * t3 + 3.0 * t2) * p1
+        + (t3 - t2) * m1
+}
+
+#[cfg(feature = "serde")]
+impl<T: Ux + Copy> serde::Serialize for Table<T>
+where
+    T::Rep: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+
+        let mut tuple = serializer.serialize_tuple(TABLE_LEN)?;
+        for cell in self.cells() {
+            tuple.serialize_element(&cell.get().into())?;
+        }
+        tuple.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Ux + Copy> serde::Deserialize<'de> for Table<T>
+where
+    T::Rep: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use core::marker::PhantomData;
+        use serde::de::{Error, SeqAccess, Visitor};
+
+        struct TableVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Ux + Copy> Visitor<'de> for TableVisitor<T>
+        where
+            T::Rep: serde::Deserialize<'de>,
+        {
+            type Value = Table<T>;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "a tuple of {} DAC values", TABLE_LEN)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let table = Table::new();
+                for (i, cell) in table.cells().enumerate() {
+                    let rep: T::Rep = seq
+                        .next_element()?
+                        .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+                    let value =
+                        T::try_from(rep).map_err(|_| A::Error::custom("DAC value out of range"))?;
+                    cell.set(value);
+                }
+                Ok(table)
+            }
+        }
+
+        deserializer.deserialize_tuple(TABLE_LEN, TableVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,120 @@
+use core::num::NonZeroU16;
+
+use num_traits::float::FloatCore;
+
+use crate::{domain::ReferencePitch, key_frequencies::exp2f, period::Period};
+
+pub(crate) const A4: f32 = 69.0;
+
+/// Maps MIDI notes to the frequencies `OscfExt` tunes the anchors to.
+pub trait TuningSystem {
+    /// Frequency in Hz of MIDI `note`.
+    fn frequency(&self, note: f32) -> f32;
+
+    /// Target period of MIDI `note`, `None` when it does not fit the counter.
+    fn period<P: Period>(&self, note: usize) -> Option<P> {
+        P::from_frequency(self.frequency(note as f32) as f64)
+    }
+}
+
+impl<T: TuningSystem + ?Sized> TuningSystem for &T {
+    fn frequency(&self, note: f32) -> f32 {
+        (**self).frequency(note)
+    }
+
+    fn period<P: Period>(&self, note: usize) -> Option<P> {
+        (**self).period(note)
+    }
+}
+
+/// Equal division of the octave into `divisions` steps, one per MIDI note,
+/// with A4 at `reference`. The default is 12-TET at 440 Hz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edo {
+    pub reference: ReferencePitch,
+    pub divisions: NonZeroU16,
+}
+
+impl Edo {
+    pub const fn new(reference: ReferencePitch, divisions: NonZeroU16) -> Self {
+        Self {
+            reference,
+            divisions,
+        }
+    }
+}
+
+impl Default for Edo {
+    fn default() -> Self {
+        let twelve = NonZeroU16::new(12).unwrap_or_else(|| panic!("should never happen"));
+        Self::new(ReferencePitch::clamp(440.0), twelve)
+    }
+}
+
+impl TuningSystem for Edo {
+    fn frequency(&self, note: f32) -> f32 {
+        exp2f((note - A4) / self.divisions.get() as f32) * self.reference.get()
+    }
+}
+
+/// Ratios of the `N` degrees above `root`, repeating every octave. The root
+/// sits where 12-TET at `reference` puts it, fractional notes bend from the
+/// key below in 12-TET cents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JustIntonation<const N: usize = 12> {
+    pub reference: ReferencePitch,
+    /// MIDI note of the root key.
+    pub root: u8,
+    /// Numerator and denominator per degree, starting with the root.
+    pub ratios: [(u16, u16); N],
+}
+
+impl JustIntonation {
+    /// The common 5-limit 12 note scale.
+    pub const FIVE_LIMIT: [(u16, u16); 12] = [
+        (1, 1),
+        (16, 15),
+        (9, 8),
+        (6, 5),
+        (5, 4),
+        (4, 3),
+        (45, 32),
+        (3, 2),
+        (8, 5),
+        (5, 3),
+        (9, 5),
+        (15, 8),
+    ];
+
+    pub const fn five_limit(reference: ReferencePitch, root: u8) -> Self {
+        Self::new(reference, root, Self::FIVE_LIMIT)
+    }
+}
+
+impl<const N: usize> JustIntonation<N> {
+    /// Panics if a ratio has a zero denominator.
+    pub const fn new(reference: ReferencePitch, root: u8, ratios: [(u16, u16); N]) -> Self {
+        const { assert!(N > 0) };
+        let mut i = 0;
+        while i < N {
+            assert!(ratios[i].1 != 0, "ratio denominators must not be zero");
+            i += 1;
+        }
+        Self {
+            reference,
+            root,
+            ratios,
+        }
+    }
+}
+
+impl<const N: usize> TuningSystem for JustIntonation<N> {
+    fn frequency(&self, note: f32) -> f32 {
+        let root = exp2f((self.root as f32 - A4) / 12.0) * self.reference.get();
+        let key = FloatCore::floor(note);
+        let steps = key as i32 - self.root as i32;
+        let octave = steps.div_euclid(N as i32);
+        let (num, den) = self.ratios[steps.rem_euclid(N as i32) as usize];
+        root * exp2f(octave as f32 + (note - key) / 12.0) * num as f32 / den as f32
+    }
+}
@@ -0,0 +1,206 @@
+use core::{
+    cell::Cell,
+    fmt,
+    future::poll_fn,
+    ops::{Add, Sub},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use embassy_futures::join::join_array;
+use num_integer::Average;
+use num_traits::One;
+use uxt::Ux;
+
+use crate::{
+    cache::NoCache,
+    config::TuneConfig,
+    error::{MeasureError, TuneError},
+    measurement::MeasurementStrategy,
+    progress::TuneObserver,
+    table::TuningTable,
+    tuning::TuningSystem,
+    OscfExt, TryOscf,
+};
+
+/// Serialises period readings of voices that share one (multiplexed)
+/// frequency counter. A voice holds it from routing the counter to its
+/// output until the reading completes, so the others can write their DACs
+/// and settle meanwhile.
+#[derive(Debug, Default)]
+pub struct SharedCounter {
+    busy: Cell<bool>,
+    waiter: WakerSlot,
+}
+
+impl SharedCounter {
+    pub const fn new() -> Self {
+        Self {
+            busy: Cell::new(false),
+            waiter: WakerSlot::new(),
+        }
+    }
+
+    /// Waits until the voice holding the counter releases it.
+    pub async fn lock(&self) -> CounterGuard<'_> {
+        poll_fn(|cx| {
+            if self.busy.replace(true) {
+                self.waiter.register(cx.waker());
+                Poll::Pending
+            } else {
+                Poll::Ready(CounterGuard(self))
+            }
+        })
+        .await
+    }
+}
+
+pub struct CounterGuard<'a>(&'a SharedCounter);
+
+impl<'a> Drop for CounterGuard<'a> {
+    fn drop(&mut self) {
+        self.0.busy.set(false);
+        self.0.waiter.wake();
+    }
+}
+
+// The task waiting for a lock. Waiters are usually polled by one task, e.g.
+// the voices of `tune_voices`, so they share its waker. A waiter from another
+// task takes the slot over and wakes the previous one to register again.
+#[derive(Default)]
+pub(crate) struct WakerSlot(Cell<Option<Waker>>);
+
+impl fmt::Debug for WakerSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WakerSlot").finish_non_exhaustive()
+    }
+}
+
+impl WakerSlot {
+    pub(crate) const fn new() -> Self {
+        Self(Cell::new(None))
+    }
+
+    pub(crate) fn register(&self, waker: &Waker) {
+        match self.0.take() {
+            Some(old) if old.will_wake(waker) => self.0.set(Some(old)),
+            old => {
+                self.0.set(Some(waker.clone()));
+                if let Some(old) = old {
+                    old.wake();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = self.0.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Tables and ratio of one voice, as produced by `OscfExt::tune_midi_frequencies`.
+#[derive(Debug)]
+pub struct VoiceTuning<A, D> {
+    pub main: A,
+    pub offset: A,
+    pub ratio: D,
+}
+
+pub type VoiceResult<O, A> = Result<
+    VoiceTuning<A, <O as TryOscf>::DacValue>,
+    TuneError<<O as TryOscf>::Error, <O as TryOscf>::Period>,
+>;
+
+/// Tunes every voice concurrently. Each voice's `try_get_period` must read
+/// the counter behind `counter`, routing it to that voice first if it is
+/// multiplexed. The DAC writes and settling of one voice overlap with the
+/// readings of the others.
+pub async fn tune_voices<O, A, const N: usize, const V: usize>(
+    voices: &mut [O; V],
+    counter: &SharedCounter,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<O::DacValue, O::Period>,
+    >,
+) -> [VoiceResult<O, A>; V]
+where
+    O: TryOscf,
+    <O::DacValue as Ux>::Rep: Add<Output = <O::DacValue as Ux>::Rep>
+        + Sub<Output = <O::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy,
+    A: TuningTable<N, DacValue = O::DacValue> + Default,
+{
+    join_array(voices.each_mut().map(|o| async move {
+        let mut voice = Voice { o, counter };
+        let mut main = A::default();
+        let mut offset = A::default();
+        let ratio = voice
+            .tune_midi_frequencies(&mut main, &mut offset, &NoCache::new(), config)
+            .await?;
+        Ok(VoiceTuning {
+            main,
+            offset,
+            ratio,
+        })
+    }))
+    .await
+}
+
+// A voice whose readings hold the shared counter.
+struct Voice<'a, O: ?Sized> {
+    o: &'a mut O,
+    counter: &'a SharedCounter,
+}
+
+impl<'a, O: TryOscf + ?Sized> TryOscf for Voice<'a, O> {
+    type DacValue = O::DacValue;
+    type Period = O::Period;
+    type Error = O::Error;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Self::Period, MeasureError<Self::Error>> {
+        let _guard = self.counter.lock().await;
+        self.o.try_get_period(timeout).await
+    }
+
+    async fn try_set_main_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.o.try_set_main_dac(value).await
+    }
+
+    async fn try_set_offset_dac(&mut self, value: Self::DacValue) -> Result<(), Self::Error> {
+        self.o.try_set_offset_dac(value).await
+    }
+
+    // Runs without the counter, so other voices read while this one settles.
+    async fn settle_writes(&mut self) {
+        self.o.settle_writes().await
+    }
+
+    async fn try_delay(
+        &mut self,
+        duration: Duration,
+        timeout: Duration,
+    ) -> Result<(), MeasureError<Self::Error>> {
+        // The default delay reads the counter, so it holds it like a reading.
+        let _guard = self.counter.lock().await;
+        self.o.try_delay(duration, timeout).await
+    }
+}
+
+impl<'a, O: TryOscf + ?Sized> OscfExt for Voice<'a, O> where
+    <Self::DacValue as Ux>::Rep: Add<Output = <Self::DacValue as Ux>::Rep>
+        + Sub<Output = <Self::DacValue as Ux>::Rep>
+        + One
+        + PartialOrd
+        + Average
+        + Copy
+{
+}
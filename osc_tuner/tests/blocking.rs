@@ -0,0 +1,61 @@
+use osc_tuner::{
+    blocking::OscfBlocking,
+    cache::NoCache,
+    config::TuneConfig,
+    period::MicrosPeriod,
+    sim::{block_on, SimulatedOscf},
+    table::Table,
+    TryOscf,
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+// Superloop firmware driving the simulator without an executor.
+struct Superloop(SimulatedOscf<u12>);
+
+impl OscfBlocking for Superloop {
+    type DacValue = u12;
+    type Error = core::convert::Infallible;
+
+    fn get_period(
+        &mut self,
+        timeout: core::time::Duration,
+    ) -> Result<MicrosPeriod, osc_tuner::error::MeasureError<Self::Error>> {
+        block_on(self.0.try_get_period(timeout))
+    }
+
+    fn set_main_dac(&mut self, value: u12) -> Result<(), Self::Error> {
+        block_on(self.0.try_set_main_dac(value))
+    }
+
+    fn set_offset_dac(&mut self, value: u12) -> Result<(), Self::Error> {
+        block_on(self.0.try_set_offset_dac(value))
+    }
+}
+
+#[test]
+fn blocking_tune_matches_async_tune() {
+    let mut osc = Superloop(SimulatedOscf::new(realistic()));
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio = osc
+        .tune_midi_frequencies(
+            &mut main,
+            &mut offset,
+            &NoCache::new(),
+            &TuneConfig::default(),
+        )
+        .unwrap();
+
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    let mut alone = SimulatedOscf::new(realistic());
+    assert_same_tables(&tuned, &tune(&mut alone).unwrap());
+    assert_eq!(osc.0.measurements(), alone.measurements());
+}
@@ -0,0 +1,121 @@
+use core::{cell::Cell, time::Duration};
+
+use osc_tuner::{
+    cache::{
+        Cache, CacheStats, DirectMappedCache, FixedCache, Generation, LruCache, MaxAge,
+        SearchPathCache,
+    },
+    period::MicrosPeriod,
+};
+use uxt::u12;
+
+fn code(n: u16) -> u12 {
+    u12::new(n)
+}
+
+fn period(n: u32) -> MicrosPeriod {
+    MicrosPeriod::new(n).unwrap()
+}
+
+#[test]
+fn lru_evicts_least_recently_used() {
+    let cache = LruCache::<u12, 2>::new();
+    cache.set(code(1), period(10));
+    cache.set(code(2), period(20));
+    assert_eq!(cache.get(code(1)), Some(period(10)));
+    cache.set(code(3), period(30));
+
+    assert_eq!(cache.get(code(2)), None);
+    assert_eq!(cache.get(code(1)), Some(period(10)));
+    assert_eq!(cache.get(code(3)), Some(period(30)));
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 3,
+            misses: 1,
+            evictions: 1,
+            expired: 0,
+        }
+    );
+}
+
+#[test]
+fn lru_overwrites_existing_code() {
+    let cache = LruCache::<u12, 2>::new();
+    cache.set(code(1), period(10));
+    cache.set(code(1), period(11));
+    cache.set(code(2), period(20));
+
+    assert_eq!(cache.get(code(1)), Some(period(11)));
+    assert_eq!(cache.get(code(2)), Some(period(20)));
+    assert_eq!(cache.stats().evictions, 0);
+}
+
+#[test]
+fn direct_mapped_keeps_one_code_per_slot() {
+    let cache = DirectMappedCache::<u12, 1>::new();
+    cache.set(code(1), period(10));
+    assert_eq!(cache.get(code(1)), Some(period(10)));
+    cache.set(code(2), period(20));
+
+    assert_eq!(cache.get(code(1)), None);
+    assert_eq!(cache.get(code(2)), Some(period(20)));
+    assert_eq!(cache.stats().evictions, 1);
+    assert_eq!(cache.stats().hit_rate(), 2.0 / 3.0);
+}
+
+#[test]
+fn search_path_keeps_shared_codes() {
+    let cache = SearchPathCache::<u12, 4, 2>::new();
+    cache.begin_search();
+    cache.set(code(0), period(40));
+    cache.set(code(4095), period(1));
+    cache.set(code(2048), period(8));
+
+    // The next search revisits the top of the path and goes elsewhere.
+    cache.begin_search();
+    assert_eq!(cache.get(code(0)), Some(period(40)));
+    assert_eq!(cache.get(code(4095)), Some(period(1)));
+    cache.set(code(1024), period(16));
+
+    // Only what the previous search visited survives this one.
+    cache.begin_search();
+    assert_eq!(cache.get(code(0)), Some(period(40)));
+    assert_eq!(cache.get(code(2048)), None);
+    assert_eq!(cache.get(code(1024)), Some(period(16)));
+    assert_eq!(cache.stats().evictions, 3);
+}
+
+#[test]
+fn invalidated_entries_are_stale() {
+    let cache = LruCache::<u12, 4, MicrosPeriod, Generation>::new();
+    cache.set(code(1), period(10));
+    cache.expiry().invalidate();
+
+    assert_eq!(cache.get(code(1)), None);
+    cache.set(code(1), period(11));
+    assert_eq!(cache.get(code(1)), Some(period(11)));
+    assert_eq!(cache.stats().expired, 1);
+    assert_eq!(cache.stats().evictions, 0);
+}
+
+#[test]
+fn old_entries_are_stale() {
+    let now = Cell::new(Duration::ZERO);
+    let cache = FixedCache::<u12, 4096, MicrosPeriod, _>::with_expiry(MaxAge::new(
+        || now.get(),
+        Duration::from_secs(60),
+    ));
+    cache.set(code(1), period(10));
+    now.set(Duration::from_secs(30));
+    cache.set(code(2), period(20));
+
+    now.set(Duration::from_secs(60));
+    assert_eq!(cache.get(code(1)), Some(period(10)));
+    now.set(Duration::from_secs(61));
+    assert_eq!(cache.get(code(1)), None);
+    assert_eq!(cache.get(code(2)), Some(period(20)));
+
+    cache.expiry().invalidate();
+    assert_eq!(cache.get(code(2)), None);
+}
@@ -0,0 +1,219 @@
+use osc_tuner::{
+    cache::NoCache,
+    calibration::CalibrationSet,
+    config::TuneConfig,
+    domain::{MidiOscDomain, ReferencePitch},
+    sim::{block_on, SimConfig, SimulatedOscf},
+    tuning::Edo,
+    TemperatureSensor,
+};
+use std::num::NonZeroU16;
+
+#[cfg(feature = "postcard")]
+use osc_tuner::{
+    calibration::{Calibration, CalibrationError, FORMAT_VERSION},
+    table::Table,
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+struct Thermometer(f64);
+
+impl TemperatureSensor for Thermometer {
+    type Error = core::convert::Infallible;
+
+    async fn read_temperature(&mut self) -> Result<f32, Self::Error> {
+        Ok(self.0 as f32)
+    }
+}
+
+#[test]
+fn calibration_set_interpolates_between_temperatures() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        tempco: 2.0,
+        ..realistic()
+    });
+    let mut set = CalibrationSet::<u12, 6>::new(0.0, 10.0);
+    let config = TuneConfig::default();
+    for temperature in [10.0, 40.0] {
+        osc.temperature = temperature;
+        let filled = block_on(set.fill_missing(
+            &mut osc,
+            &mut Thermometer(temperature),
+            &NoCache::new(),
+            &config,
+        ))
+        .unwrap();
+        assert_eq!(filled, Some(set.bin(temperature as f32)));
+    }
+    let filled =
+        block_on(set.fill_missing(&mut osc, &mut Thermometer(38.0), &NoCache::new(), &config));
+    assert_eq!(filled, Ok(None));
+    assert_eq!(set.get(1).unwrap().temperature, Some(10.0));
+
+    // Between calibrations the main code crosses converter steps the tables
+    // never saw, hence the extra headroom.
+    osc.temperature = 25.0;
+    for note in anchors() {
+        let (main, offset) = set
+            .dac_pair(MidiOscDomain::new(note).unwrap(), 0.0, 25.0)
+            .unwrap();
+        let error = 1200.0 * (osc.frequency(main, offset) / target_frequency(note)).log2();
+        assert!(
+            error.abs() <= bound(note, 2.0),
+            "note {note}: {error:.2} cents"
+        );
+    }
+
+    // Either calibration alone is 30 cents off at 25 °C.
+    let cold = set.get(1).unwrap().pitch_engine();
+    let (main, offset) = cold.dac_pair(MidiOscDomain::new(69.0).unwrap(), 0.0);
+    let error = 1200.0 * (osc.frequency(main, offset) / 440.0).log2();
+    assert!(error > 25.0, "{error:.2} cents");
+}
+
+// Warms up by `drift` °C between readings.
+struct Drifting(f32, f32);
+
+impl TemperatureSensor for Drifting {
+    type Error = core::convert::Infallible;
+
+    async fn read_temperature(&mut self) -> Result<f32, Self::Error> {
+        let reading = self.0;
+        self.0 += self.1;
+        Ok(reading)
+    }
+}
+
+#[test]
+fn fill_missing_keeps_the_bin_it_checked() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let mut set = CalibrationSet::<u12, 6>::new(0.0, 10.0);
+    let tuning = Edo::new(
+        ReferencePitch::new(415.0).unwrap(),
+        NonZeroU16::new(12).unwrap(),
+    );
+    let config = TuneConfig::default().with_tuning(tuning);
+    let filled =
+        block_on(set.fill_missing(&mut osc, &mut Drifting(14.0, 4.0), &NoCache::new(), &config));
+    assert_eq!(filled, Ok(Some(1)));
+    assert!(set.get(2).is_none());
+    let calibration = set.get(1).unwrap();
+    assert_eq!(calibration.temperature, Some(16.0));
+    assert!((calibration.reference_pitch - 415.0).abs() < 0.01);
+}
+
+#[cfg(feature = "postcard")]
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(feature = "postcard")]
+fn tuned_calibration() -> Calibration<u12> {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let tuned = tune(&mut osc).unwrap();
+    let mut calibration = Calibration::new(tuned.main, tuned.offset, tuned.ratio);
+    calibration.tuned_at = 7;
+    calibration
+}
+
+#[cfg(feature = "postcard")]
+fn same_tables(a: &Calibration<u12>, b: &Calibration<u12>) -> bool {
+    let codes = |table: &Table<u12>| table.cells().map(|cell| cell.get()).collect::<Vec<_>>();
+    a.ratio == b.ratio && codes(&a.main) == codes(&b.main) && codes(&a.offset) == codes(&b.offset)
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn calibration_blob_round_trips() {
+    let mut calibration = tuned_calibration();
+    calibration.reference_pitch = 415.0;
+    calibration.temperature = Some(23.5);
+    let mut buf = [0; Calibration::<u12>::MAX_SIZE];
+    let blob = calibration.to_bytes(&mut buf).unwrap();
+
+    let loaded = Calibration::<u12>::from_bytes(blob).unwrap();
+    assert!(same_tables(&loaded, &calibration));
+    assert_eq!(loaded.reference_pitch, 415.0);
+    assert_eq!(loaded.tuned_at, 7);
+    assert_eq!(loaded.temperature, Some(23.5));
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn damaged_calibration_blobs_are_rejected() {
+    let mut buf = [0; Calibration::<u12>::MAX_SIZE];
+    let blob = tuned_calibration().to_bytes(&mut buf).unwrap().to_vec();
+
+    // Every flipped payload bit is caught by the CRC, before decoding.
+    for at in 8..blob.len() - 4 {
+        let mut flipped = blob.clone();
+        flipped[at] ^= 0x10;
+        assert_eq!(
+            Calibration::<u12>::from_bytes(&flipped).err(),
+            Some(CalibrationError::CrcMismatch),
+            "byte {at}"
+        );
+    }
+
+    for len in [0, 9, blob.len() - 1] {
+        assert_eq!(
+            Calibration::<u12>::from_bytes(&blob[..len]).err(),
+            Some(CalibrationError::BufferTooSmall),
+            "{len} bytes"
+        );
+    }
+
+    let mut magic = blob.clone();
+    magic[0] = b'X';
+    assert_eq!(
+        Calibration::<u12>::from_bytes(&magic).err(),
+        Some(CalibrationError::BadMagic)
+    );
+
+    for version in [0, FORMAT_VERSION + 1] {
+        let mut future = blob.clone();
+        future[4] = version;
+        assert_eq!(
+            Calibration::<u12>::from_bytes(&future).err(),
+            Some(CalibrationError::UnsupportedVersion(version))
+        );
+    }
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn version_1_calibration_blobs_still_load() {
+    let calibration = tuned_calibration();
+    let mut buf = [0; Calibration::<u12>::MAX_SIZE];
+    let blob = calibration.to_bytes(&mut buf).unwrap();
+
+    // Version 1 is version 3 without the payload length in the header and
+    // the trailing `None` temperature.
+    let payload_end = blob.len() - 4;
+    assert_eq!(blob[payload_end - 1], 0);
+    let mut v1 = blob[..6].to_vec();
+    v1.extend_from_slice(&blob[8..payload_end - 1]);
+    v1[4] = 1;
+    let crc = crc32(&v1);
+    v1.extend_from_slice(&crc.to_le_bytes());
+
+    let loaded = Calibration::<u12>::from_bytes(&v1).unwrap();
+    assert_eq!(loaded.tuned_at, 7);
+    assert_eq!(loaded.temperature, None);
+    assert!(same_tables(&loaded, &calibration));
+}
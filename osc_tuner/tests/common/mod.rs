@@ -0,0 +1,138 @@
+//! Simulator helpers shared by the test files.
+#![allow(dead_code)]
+
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    domain::MidiOscDomain,
+    error::TuneError,
+    measurement::MeasurementStrategy,
+    parts::{Dac, PeriodMeter},
+    period::MicrosPeriod,
+    progress::TuneObserver,
+    sim::{block_on, SimConfig, SimulatedOscf},
+    table::Table,
+    tuning::{Edo, TuningSystem},
+    voices::VoiceTuning,
+    OscfExt, TryOscf,
+};
+use std::cell::RefCell;
+
+use uxt::u12;
+
+pub type Tuned = VoiceTuning<Table<u12>, u12>;
+
+pub fn tune(osc: &mut SimulatedOscf<u12>) -> Result<Tuned, TuneError<core::convert::Infallible>> {
+    tune_with(osc, &TuneConfig::default())
+}
+
+pub fn tune_with(
+    osc: &mut SimulatedOscf<u12>,
+    config: &TuneConfig<
+        impl MeasurementStrategy,
+        impl TuningSystem,
+        impl TuneObserver<u12, MicrosPeriod>,
+    >,
+) -> Result<Tuned, TuneError<core::convert::Infallible>> {
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio =
+        block_on(osc.tune_midi_frequencies(&mut main, &mut offset, &NoCache::new(), config))?;
+    Ok(Tuned {
+        main,
+        offset,
+        ratio,
+    })
+}
+
+pub fn target_frequency(note: f32) -> f64 {
+    440.0 * ((note as f64 - 69.0) / 12.0).exp2()
+}
+
+// Targets and readings are both rounded to whole microseconds, so allow one
+// microsecond expressed in cents plus some headroom.
+pub fn bound(note: f32, headroom: f64) -> f64 {
+    let micros = 1e6 / target_frequency(note);
+    1200.0 * (1.0 + 1.0 / micros).log2() + headroom
+}
+
+pub fn cents_error(osc: &SimulatedOscf<u12>, tuned: &Tuned, note: f32) -> f64 {
+    cents_error_in(osc, tuned, note, Edo::default())
+}
+
+pub fn cents_error_in(
+    osc: &SimulatedOscf<u12>,
+    tuned: &Tuned,
+    note: f32,
+    tuning: impl TuningSystem,
+) -> f64 {
+    let target = tuning.frequency(note) as f64;
+    let engine = osc_tuner::pitch::PitchEngine::new(&tuned.main, &tuned.offset, tuned.ratio)
+        .with_tuning(tuning);
+    let (main, offset) = engine.dac_pair(MidiOscDomain::new(note).unwrap(), 0.0);
+    1200.0 * (osc.frequency(main, offset) / target).log2()
+}
+
+pub fn anchors() -> impl Iterator<Item = f32> {
+    [12.0, 24.0].into_iter().chain((60..=120).map(|n| n as f32))
+}
+
+pub fn assert_anchors(osc: &SimulatedOscf<u12>, tuned: &Tuned, headroom: f64) {
+    for note in anchors() {
+        let error = cents_error(osc, tuned, note);
+        assert!(
+            error.abs() <= bound(note, headroom),
+            "note {note}: {error:.2} cents"
+        );
+    }
+}
+
+pub fn realistic() -> SimConfig {
+    SimConfig {
+        scale_error: 0.015,
+        offset_error: 0.07,
+        inl: 3.0,
+        dnl: 0.4,
+        reset_time: 2e-6,
+        ..SimConfig::default()
+    }
+}
+
+// Meter and DACs of one simulated board, as separate drivers. The simulator
+// never suspends, so holding the borrow across its awaits is fine.
+pub struct Counter<'a>(pub &'a RefCell<SimulatedOscf<u12>>);
+pub struct Channel<'a>(pub &'a RefCell<SimulatedOscf<u12>>, pub bool);
+
+#[allow(clippy::await_holding_refcell_ref)]
+impl PeriodMeter for Counter<'_> {
+    type Error = core::convert::Infallible;
+
+    async fn measure_period(
+        &mut self,
+        timeout: core::time::Duration,
+    ) -> Result<MicrosPeriod, osc_tuner::error::MeasureError<Self::Error>> {
+        self.0.borrow_mut().try_get_period(timeout).await
+    }
+}
+
+#[allow(clippy::await_holding_refcell_ref)]
+impl Dac for Channel<'_> {
+    type Value = u12;
+    type Error = core::convert::Infallible;
+
+    async fn write(&mut self, value: u12) -> Result<(), Self::Error> {
+        let mut osc = self.0.borrow_mut();
+        if self.1 {
+            osc.try_set_offset_dac(value).await
+        } else {
+            osc.try_set_main_dac(value).await
+        }
+    }
+}
+
+pub fn assert_same_tables(tuned: &Tuned, expected: &Tuned) {
+    let codes = |table: &Table<u12>| table.cells().map(|cell| cell.get()).collect::<Vec<_>>();
+    assert_eq!(tuned.ratio, expected.ratio);
+    assert_eq!(codes(&tuned.main), codes(&expected.main));
+    assert_eq!(codes(&tuned.offset), codes(&expected.offset));
+}
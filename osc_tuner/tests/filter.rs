@@ -0,0 +1,79 @@
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    domain::{MidiFilterDomain, ReferencePitch},
+    filter::{CutoffTable, VcfExt},
+    period::Ticks,
+    sim::{block_on, SimConfig, SimulatedOscf},
+    tuning::{Edo, TuningSystem},
+};
+use std::num::NonZeroU16;
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+fn vcf() -> SimulatedOscf<u12, Ticks<72_000_000>> {
+    SimulatedOscf::new(SimConfig {
+        codes_per_octave: 360.0,
+        ..realistic()
+    })
+}
+
+#[test]
+fn filter_cutoff_reaches_20khz() {
+    let mut vcf = vcf();
+    let mut table = CutoffTable::<u12>::new();
+    block_on(vcf.calibrate_cutoff(
+        &mut table,
+        &NoCache::<u12, Ticks<72_000_000>>::new(),
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    for note in [12.0, 30.5, 60.0, 99.0, 127.0, 133.3, 135.0, 135.076] {
+        let cutoff = table.dac_for_cutoff(MidiFilterDomain::new(note).unwrap());
+        let error = 1200.0 * (vcf.frequency(cutoff, u12::new(0)) / target_frequency(note)).log2();
+        // One cutoff step is 3.3 cents, DNL and the bow add a little between anchors.
+        assert!(error.abs() <= 4.0, "note {note}: {error:.2} cents");
+    }
+    let top = table.dac_for_cutoff(MidiFilterDomain::max());
+    assert!((vcf.frequency(top, u12::new(0)) - 20_000.0).abs() < 50.0);
+}
+
+#[test]
+fn filter_cutoff_tuning_is_explicit() {
+    let baroque = Edo::new(
+        ReferencePitch::new(415.0).unwrap(),
+        NonZeroU16::new(12).unwrap(),
+    );
+    let cache = NoCache::<u12, Ticks<72_000_000>>::new();
+    let calibrate = |tuning: Option<&Edo>| {
+        let mut vcf = vcf();
+        let mut table = CutoffTable::<u12>::new();
+        // The oscillator tuning in the config never moves the cutoff.
+        let config = TuneConfig::default().with_tuning(baroque);
+        match tuning {
+            None => block_on(vcf.calibrate_cutoff(&mut table, &cache, &config)),
+            Some(tuning) => block_on(vcf.calibrate_cutoff_in(&mut table, &cache, tuning, &config)),
+        }
+        .unwrap();
+        table.anchors.map(|a| a.get())
+    };
+
+    let mut reference = CutoffTable::<u12>::new();
+    block_on(vcf().calibrate_cutoff(&mut reference, &cache, &TuneConfig::default())).unwrap();
+    assert_eq!(calibrate(None), reference.anchors.map(|a| a.get()));
+    assert_eq!(calibrate(Some(&Edo::default())), calibrate(None));
+
+    // Anchors settle within one cutoff step plus the search tolerance.
+    let bound = 1200.0 / 360.0 + 1200.0 * (1.0 + TuneConfig::default().tolerance as f64).log2();
+    let vcf = vcf();
+    for (i, cutoff) in calibrate(Some(&baroque)).into_iter().enumerate() {
+        let note = (12 + 3 * i) as f32;
+        let error =
+            1200.0 * (vcf.frequency(cutoff, u12::new(0)) / baroque.frequency(note) as f64).log2();
+        assert!(error.abs() <= bound, "note {note}: {error:.2} cents");
+    }
+}
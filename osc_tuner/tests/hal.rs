@@ -0,0 +1,221 @@
+use core::{
+    cell::Cell,
+    future::{poll_fn, Future},
+    pin::pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Wake,
+};
+
+use embedded_hal_mock::eh1::{
+    delay::{CheckedDelay, Transaction as DelayTransaction},
+    spi::{Mock as SpiMock, Transaction as SpiTransaction},
+};
+use osc_tuner::{
+    error::MeasureError,
+    hal::{Ad5668, CaptureMeter, Dac8564, DacChip, DelaySettle, Gain, InputCapture, Mcp4x22},
+    parts::{Composed, Dac, PeriodMeter},
+    period::{MicrosPeriod, Period, Ticks},
+    sim::block_on,
+    TryOscf,
+};
+use uxt::{u12, u2};
+
+fn writes(frames: &[&[u8]]) -> Vec<SpiTransaction<u8>> {
+    frames
+        .iter()
+        .flat_map(|frame| {
+            [
+                SpiTransaction::transaction_start(),
+                SpiTransaction::write_vec(frame.to_vec()),
+                SpiTransaction::transaction_end(),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn mcp4x22_frames() {
+    let spi = SpiMock::new(&writes(&[&[0x3A, 0xBC], &[0xD0, 0x01], &[0xFF, 0xFF]]));
+    let chip = DacChip::new(spi, Mcp4x22::default());
+    block_on(chip.channel(0).write(u12::new(0xABC))).unwrap();
+    let buffered = DacChip::new(
+        chip.into_inner(),
+        Mcp4x22 {
+            gain: Gain::Double,
+            buffered: true,
+        },
+    );
+    block_on(buffered.channel(1).write(u12::new(0x001))).unwrap();
+    let single = DacChip::new(
+        buffered.into_inner(),
+        Mcp4x22 {
+            gain: Gain::Single,
+            buffered: true,
+        },
+    );
+    block_on(single.channel(1).write(u12::MAX)).unwrap();
+    single.into_inner().done();
+}
+
+#[test]
+fn dac8564_frames() {
+    let spi = SpiMock::new(&writes(&[&[0x10, 0x12, 0x34], &[0xD6, 0xFF, 0xFF]]));
+    let chip = DacChip::new(spi, Dac8564::default());
+    block_on(chip.channel(0).write(0x1234)).unwrap();
+    let addressed = DacChip::new(
+        chip.into_inner(),
+        Dac8564 {
+            address: u2::new(3),
+        },
+    );
+    block_on(addressed.channel(3).write(0xFFFF)).unwrap();
+    addressed.into_inner().done();
+}
+
+#[test]
+fn ad5668_frames() {
+    let spi = SpiMock::new(&writes(&[
+        &[0x03, 0x01, 0x23, 0x40],
+        &[0x03, 0x7F, 0xFF, 0xF0],
+    ]));
+    let chip = DacChip::new(spi, Ad5668);
+    block_on(chip.channel(0).write(0x1234)).unwrap();
+    block_on(chip.channel(7).write(0xFFFF)).unwrap();
+    chip.into_inner().done();
+}
+
+#[test]
+#[should_panic]
+fn channel_out_of_range() {
+    DacChip::new(SpiMock::<u8>::new(&[]), Mcp4x22::default()).channel(2);
+}
+
+// Replays captured counts of a 1 MHz timer, then stops seeing edges.
+struct Counts(VecDeque<u32>);
+
+impl InputCapture for Counts {
+    type Error = core::convert::Infallible;
+    const TICK_HZ: u32 = 1_000_000;
+
+    async fn capture(&mut self, _timeout: Duration) -> Result<Option<u32>, Self::Error> {
+        Ok(self.0.pop_front())
+    }
+}
+
+fn meter<P: Period>(counts: &[u32]) -> CaptureMeter<Counts, P> {
+    CaptureMeter::new(Counts(counts.iter().copied().collect()))
+}
+
+#[test]
+fn capture_meter_counts_edges() {
+    let timeout = Duration::from_millis(100);
+    let period = block_on(meter::<MicrosPeriod>(&[100, 2373]).measure_period(timeout));
+    assert_eq!(period.map(Period::ticks), Ok(2273));
+
+    // Wraps around and rescales to the period's own counter.
+    let counts = [u32::MAX - 99, 900, 1900, 2900, 3900];
+    let period = block_on(meter::<Ticks<16_000_000, 4>>(&counts).measure_period(timeout));
+    assert_eq!(period.map(Period::ticks), Ok(4000 * 16));
+
+    let period = block_on(meter::<MicrosPeriod>(&[]).measure_period(timeout));
+    assert_eq!(period, Err(MeasureError::NoEdges));
+    let period = block_on(meter::<MicrosPeriod>(&[100]).measure_period(timeout));
+    assert_eq!(period, Err(MeasureError::Timeout));
+    let period = block_on(meter::<MicrosPeriod>(&[0, 200_000]).measure_period(timeout));
+    assert_eq!(period, Err(MeasureError::Timeout));
+}
+
+#[test]
+fn composed_board_settles_once_per_write_pair() {
+    let spi = SpiMock::new(&writes(&[&[0x38, 0x00], &[0xB0, 0x40]]));
+    let chip = DacChip::new(spi, Mcp4x22::default());
+    let delay = CheckedDelay::new(&[DelayTransaction::async_delay_us(500)]);
+    let mut osc = Composed::new(
+        meter::<MicrosPeriod>(&[0, 1000, 5000, 6000]),
+        chip.channel(0),
+        Some(chip.channel(1)),
+    )
+    .with_settle(DelaySettle {
+        delay,
+        time: Duration::from_micros(500),
+    });
+
+    let timeout = Duration::from_millis(10);
+    block_on(osc.try_set_main_dac(u12::new(0x800))).unwrap();
+    block_on(osc.try_set_offset_dac(u12::new(0x040))).unwrap();
+    assert_eq!(
+        block_on(osc.try_get_period(timeout)).map(Period::ticks),
+        Ok(1000)
+    );
+    assert_eq!(
+        block_on(osc.try_get_period(timeout)).map(Period::ticks),
+        Ok(1000)
+    );
+
+    osc.settle.delay.done();
+    drop(osc);
+    chip.into_inner().done();
+}
+
+// Holds every write pending until the test lets it complete.
+struct SlowSpi<'a>(&'a Cell<bool>);
+
+impl embedded_hal_async::spi::ErrorType for SlowSpi<'_> {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_hal_async::spi::SpiDevice for SlowSpi<'_> {
+    async fn transaction(
+        &mut self,
+        _operations: &mut [embedded_hal_async::spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        poll_fn(|_| {
+            if self.0.get() {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+#[test]
+fn waiting_channel_sleeps_until_the_chip_is_free() {
+    struct Wakes(AtomicUsize);
+
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let wakes = Arc::new(Wakes(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let done = Cell::new(false);
+    let chip = DacChip::new(SlowSpi(&done), Mcp4x22::default());
+    let mut first = chip.channel(0);
+    let mut second = chip.channel(1);
+    let mut writing = pin!(first.write(u12::new(1)));
+    let mut waiting = pin!(second.write(u12::new(2)));
+    assert!(writing.as_mut().poll(&mut cx).is_pending());
+    for _ in 0..3 {
+        assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    }
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 0);
+
+    done.set(true);
+    assert!(writing.as_mut().poll(&mut cx).is_ready());
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert!(waiting.as_mut().poll(&mut cx).is_ready());
+}
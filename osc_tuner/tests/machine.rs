@@ -0,0 +1,75 @@
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    error::{MeasureError, TuneError},
+    machine::{Action, Link},
+    period::MicrosPeriod,
+    search::SearchStrategy,
+    sim::{block_on, SimulatedOscf},
+    table::Table,
+    TryOscf,
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[test]
+fn state_machine_matches_async_tune() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let link = Link::new();
+    let config = TuneConfig::default().with_search(SearchStrategy::Interpolation);
+    let cache = NoCache::new();
+    let ratio = {
+        let mut machine =
+            core::pin::pin!(link.tune_midi_frequencies(&mut main, &mut offset, &cache, &config));
+        let mut reading = None;
+        let ratio = loop {
+            match machine.as_mut().step(reading.take()) {
+                Action::SetMainDac(value) => block_on(osc.try_set_main_dac(value)).unwrap(),
+                Action::SetOffsetDac(value) => block_on(osc.try_set_offset_dac(value)).unwrap(),
+                Action::Measure(timeout) => reading = Some(block_on(osc.try_get_period(timeout))),
+                Action::Done(result) => break result.unwrap(),
+            }
+        };
+        assert_eq!(machine.as_mut().step(None), Action::Done(Ok(ratio)));
+        ratio
+    };
+
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    let mut alone = SimulatedOscf::new(realistic());
+    assert_same_tables(&tuned, &tune_with(&mut alone, &config).unwrap());
+    assert_eq!(osc.measurements(), alone.measurements());
+}
+
+#[test]
+fn failed_dac_write_ends_the_tune() {
+    let mut main = Table::<u12>::new();
+    let mut offset = Table::new();
+    let link = Link::<u12, MicrosPeriod, u8>::new();
+    let config = TuneConfig::default();
+    let cache = NoCache::new();
+    let mut machine =
+        core::pin::pin!(link.tune_midi_frequencies(&mut main, &mut offset, &cache, &config));
+
+    assert!(matches!(
+        machine.as_mut().step(None),
+        Action::SetOffsetDac(_)
+    ));
+    let failed = Some(Err(MeasureError::Device(7)));
+    assert_eq!(
+        machine.as_mut().step(failed),
+        Action::Done(Err(TuneError::Device(7)))
+    );
+    assert_eq!(
+        machine.as_mut().step(None),
+        Action::Done(Err(TuneError::Device(7)))
+    );
+}
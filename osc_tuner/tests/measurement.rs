@@ -0,0 +1,48 @@
+use osc_tuner::{
+    config::TuneConfig,
+    measurement::{Adaptive, Median, TrimmedMean},
+    sim::{SimConfig, SimulatedOscf},
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+fn glitchy() -> SimulatedOscf<u12> {
+    SimulatedOscf::new(SimConfig {
+        jitter: 2e-4,
+        glitch_rate: 0.02,
+        ..realistic()
+    })
+    .with_seed(3)
+}
+
+#[test]
+fn single_readings_trip_over_glitches() {
+    assert!(tune(&mut glitchy()).is_err());
+}
+
+#[test]
+fn median_rejects_glitches() {
+    let mut osc = glitchy();
+    let config = TuneConfig::default().with_measurement(Median::<5>);
+    let tuned = tune_with(&mut osc, &config).unwrap();
+    assert_anchors(&osc, &tuned, 2.0);
+}
+
+#[test]
+fn trimmed_mean_rejects_glitches() {
+    let mut osc = glitchy();
+    let config = TuneConfig::default().with_measurement(TrimmedMean::<6, 2>);
+    let tuned = tune_with(&mut osc, &config).unwrap();
+    assert_anchors(&osc, &tuned, 2.0);
+}
+
+#[test]
+fn adaptive_remeasures_out_of_bracket_readings() {
+    let mut osc = glitchy();
+    let config = TuneConfig::default().with_measurement(Adaptive::<Median<3>, 4>::default());
+    let tuned = tune_with(&mut osc, &config).unwrap();
+    assert_anchors(&osc, &tuned, 2.0);
+}
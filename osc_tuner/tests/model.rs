@@ -0,0 +1,150 @@
+use osc_tuner::{
+    config::TuneConfig,
+    error::TuneError,
+    period::MicrosPeriod,
+    progress::{CancelToken, Phase, TuneEvent, TuneObserver},
+    sim::{block_on, SimConfig, SimulatedOscf},
+    table::{Table, TuningTable},
+    OscfExt,
+};
+use std::cell::RefCell;
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[test]
+fn model_fit_tunes_exponential_oscillator() {
+    let sim = SimConfig {
+        scale_error: 0.015,
+        offset_error: 0.07,
+        reset_time: 2e-6,
+        ..SimConfig::default()
+    };
+    let mut osc = SimulatedOscf::<u12>::new(sim);
+    let config = TuneConfig::default();
+    let fit = block_on(osc.fit_model::<6>(&config)).unwrap();
+    // The top readings are only as fine as one microsecond.
+    assert!(fit.max_residual() < 2.0, "{fit:?}");
+
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio = block_on(osc.tune_from_model(&fit.model, &mut main, &mut offset, &config)).unwrap();
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    assert_anchors(&osc, &tuned, 1.0);
+
+    let mut searched = SimulatedOscf::<u12>::new(sim);
+    tune(&mut searched).unwrap();
+    assert!(osc.measurements() * 20 < searched.measurements());
+}
+
+#[test]
+fn model_fit_flags_non_linear_converter() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        inl: 10.0,
+        ..realistic()
+    });
+    let fit = block_on(osc.fit_model::<8>(&TuneConfig::default())).unwrap();
+    assert!(fit.max_residual() > 5.0, "{fit:?}");
+}
+
+#[test]
+fn model_fit_skips_readings_that_time_out() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+    // The bottom octave is slower than that.
+    let config = TuneConfig {
+        timeout: core::time::Duration::from_millis(60),
+        ..TuneConfig::default()
+    };
+    let fit = block_on(osc.fit_model::<8>(&config)).unwrap();
+    assert_eq!(fit.residuals[0], None);
+    assert!(fit.residuals[1..].iter().all(Option::is_some));
+    assert!(fit.max_residual() < 2.0, "{fit:?}");
+
+    let config = TuneConfig {
+        timeout: core::time::Duration::from_micros(200),
+        ..config
+    };
+    let fit = block_on(osc.fit_model::<8>(&config));
+    assert_eq!(fit, Err(TuneError::Timeout));
+}
+
+// Anchors as the observer sees them, cancelling once it sees `cancel_at`.
+struct Anchors<'a> {
+    seen: RefCell<Vec<(Phase, usize, usize)>>,
+    cancel_at: Option<(Phase, usize)>,
+    token: &'a CancelToken,
+}
+
+impl<'a> TuneObserver<u12, MicrosPeriod> for Anchors<'a> {
+    fn event(&self, event: TuneEvent<u12, MicrosPeriod>) {
+        if let TuneEvent::Anchor {
+            phase,
+            index,
+            count,
+            ..
+        } = event
+        {
+            if Some((phase, index)) == self.cancel_at {
+                self.token.cancel();
+            }
+            self.seen.borrow_mut().push((phase, index, count));
+        }
+    }
+}
+
+#[test]
+fn model_tune_reports_progress_and_cancels() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+    let fit = block_on(osc.fit_model::<6>(&TuneConfig::default())).unwrap();
+    let token = CancelToken::new();
+    let mut tune = |cancel_at| {
+        token.reset();
+        let observer = Anchors {
+            seen: RefCell::new(Vec::new()),
+            cancel_at,
+            token: &token,
+        };
+        let config = TuneConfig::default().with_observer((&observer, &token));
+        let mut main = Table::new();
+        let mut offset = Table::new();
+        let result = block_on(osc.tune_from_model(&fit.model, &mut main, &mut offset, &config));
+        (result, main, observer.seen.into_inner())
+    };
+
+    let (result, reference, seen) = tune(None);
+    assert!(result.is_ok());
+    let expected: Vec<_> = [(Phase::Ratio, 0, 1)]
+        .into_iter()
+        .chain((0..63).map(|i| (Phase::Main, i, 63)))
+        .collect();
+    assert_eq!(seen, expected);
+
+    let (result, main, seen) = tune(Some((Phase::Main, 10)));
+    assert_eq!(
+        result,
+        Err(TuneError::Cancelled {
+            phase: Phase::Main,
+            index: 10,
+        })
+    );
+    assert_eq!(seen, expected[..12]);
+    for i in 0..10 {
+        assert_eq!(main.anchor(i).get(), reference.anchor(i).get());
+    }
+
+    token.cancel();
+    let config = TuneConfig::default().with_observer(&token);
+    assert_eq!(
+        block_on(osc.fit_model::<6>(&config)),
+        Err(TuneError::Cancelled {
+            phase: Phase::Main,
+            index: 0,
+        })
+    );
+}
@@ -0,0 +1,59 @@
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    parts::Composed,
+    sim::{block_on, SimulatedOscf},
+    table::Table,
+    OscfExt, TryOscf,
+};
+use std::{cell::RefCell, time::Duration};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[test]
+fn composed_parts_tune_like_one_oscillator() {
+    let board = RefCell::new(SimulatedOscf::<u12>::new(realistic()));
+    let settled = core::cell::Cell::new(0);
+    let mut osc = Composed::new(
+        Counter(&board),
+        Channel(&board, false),
+        Some(Channel(&board, true)),
+    )
+    .with_settle(|| {
+        settled.set(settled.get() + 1);
+        core::future::ready(())
+    });
+
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio = block_on(osc.tune_midi_frequencies(
+        &mut main,
+        &mut offset,
+        &NoCache::new(),
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    assert_same_tables(&tuned, &tune(&mut SimulatedOscf::new(realistic())).unwrap());
+
+    // Every reading of the tune follows fresh writes, so it settled exactly
+    // once per reading however many DACs were written.
+    assert_eq!(settled.get(), board.borrow().measurements());
+
+    // A main and offset write settle once, before the next reading.
+    let settled_before = settled.get();
+    block_on(osc.try_set_main_dac(u12::new(100))).unwrap();
+    block_on(osc.try_set_offset_dac(u12::new(7))).unwrap();
+    assert_eq!(settled.get(), settled_before);
+    block_on(osc.try_get_period(Duration::from_secs(1))).unwrap();
+    block_on(osc.try_get_period(Duration::from_secs(1))).unwrap();
+    assert_eq!(settled.get(), settled_before + 1);
+}
@@ -0,0 +1,105 @@
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    error::TuneError,
+    period::MicrosPeriod,
+    progress::{CancelToken, Phase, TuneEvent, TuneObserver},
+    sim::{block_on, SimulatedOscf},
+    table::Table,
+    OscfExt,
+};
+use std::cell::RefCell;
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[derive(Default)]
+struct Recorder<'a> {
+    events: RefCell<Vec<TuneEvent<u12, MicrosPeriod>>>,
+    cancel_at: Option<(Phase, usize, &'a CancelToken)>,
+}
+
+impl<'a> TuneObserver<u12, MicrosPeriod> for Recorder<'a> {
+    fn event(&self, event: TuneEvent<u12, MicrosPeriod>) {
+        if let (TuneEvent::Anchor { phase, index, .. }, Some((at, at_index, token))) =
+            (event, self.cancel_at)
+        {
+            if (phase, index) == (at, at_index) {
+                token.cancel();
+            }
+        }
+        self.events.borrow_mut().push(event);
+    }
+}
+
+#[test]
+fn progress_reports_every_anchor_and_bracket() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let recorder = Recorder::default();
+    tune_with(&mut osc, &TuneConfig::default().with_observer(&recorder)).unwrap();
+
+    let events = recorder.events.into_inner();
+    let anchors: Vec<_> = events
+        .iter()
+        .filter_map(|event| match *event {
+            TuneEvent::Anchor {
+                phase,
+                index,
+                count,
+                ..
+            } => Some((phase, index, count)),
+            _ => None,
+        })
+        .collect();
+    let expected: Vec<_> = (0..63)
+        .map(|i| (Phase::Main, i, 63))
+        .chain((0..63).map(|i| (Phase::Offset, i, 63)))
+        .chain([(Phase::Ratio, 0, 1)])
+        .collect();
+    assert_eq!(anchors, expected);
+
+    // Brackets of one search start at the first step and only shrink.
+    let mut width = u16::MAX;
+    for event in &events {
+        match *event {
+            TuneEvent::Anchor { .. } => width = u16::MAX,
+            TuneEvent::Bracket {
+                iteration,
+                low,
+                high,
+            } => {
+                let (low, high): (u16, u16) = (low.into(), high.into());
+                assert!(high - low < width, "step {iteration}");
+                width = high - low;
+            }
+        }
+    }
+}
+
+#[test]
+fn cancelled_tune_keeps_finished_anchors() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let token = CancelToken::new();
+    let recorder = Recorder {
+        cancel_at: Some((Phase::Offset, 5, &token)),
+        ..Recorder::default()
+    };
+    let config = TuneConfig::default().with_observer((&recorder, &token));
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let result =
+        block_on(osc.tune_midi_frequencies(&mut main, &mut offset, &NoCache::new(), &config));
+
+    assert_eq!(
+        result,
+        Err(TuneError::Cancelled {
+            phase: Phase::Offset,
+            index: 5,
+        })
+    );
+    let reference = tune(&mut SimulatedOscf::<u12>::new(realistic())).unwrap();
+    let codes = |table: &Table<u12>| table.cells().map(|cell| cell.get()).collect::<Vec<_>>();
+    assert_eq!(codes(&main), codes(&reference.main));
+}
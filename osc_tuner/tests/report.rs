@@ -0,0 +1,51 @@
+use osc_tuner::{
+    config::TuneConfig,
+    measurement::Median,
+    sim::{block_on, SimConfig, SimulatedOscf},
+    OscfExt,
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[test]
+fn report_passes_fresh_tuning() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let tuned = tune(&mut osc).unwrap();
+    let config = TuneConfig::default().with_measurement(Median::<3>);
+    let report =
+        block_on(osc.verify_midi_frequencies(&tuned.main, &tuned.offset, 1.0, &config)).unwrap();
+
+    assert!(report.passed(), "{:?}", report.failures().next());
+    for (note, offset) in report.main.iter().zip(report.offset.iter()) {
+        assert_eq!(note.note, offset.note);
+        assert_eq!(offset.measurements, 3);
+        // Microsecond rounding dominates at the top, the offset DAC below.
+        assert!(offset.cents.abs() <= bound(offset.note as f32, 0.5) as f32);
+    }
+    assert_eq!(report.offset[0].note, 12);
+    assert_eq!(report.offset[62].note, 120);
+}
+
+#[test]
+fn report_flags_drift() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        tempco: 2.0,
+        ..realistic()
+    });
+    let tuned = tune(&mut osc).unwrap();
+    osc.temperature += 10.0;
+    let report = block_on(osc.verify_midi_frequencies(
+        &tuned.main,
+        &tuned.offset,
+        15.0,
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    assert!(!report.passed());
+    assert!(report.max_error() > 15.0);
+    assert!(report.failures().all(|note| note.cents > 0.0));
+}
@@ -0,0 +1,124 @@
+use osc_tuner::{
+    scala::{KeyboardMap, ScalaErrorKind, ScalaTuning, Scale},
+    tuning::TuningSystem,
+};
+
+const MEANTONE: &[u8] = b"! meanquar.scl
+!
+1/4-comma meantone scale. Pietro Aaron's temperament (1523)
+ 12
+!
+ 76.04900
+ 193.15686
+ 310.26471
+ 5/4
+ 503.42157
+ 579.47057
+ 696.57843
+ 25/16
+ 889.73529
+ 1006.84314
+ 1082.89214
+ 2/1
+";
+
+const WHITE_KEYS: &[u8] = b"! white keys only, A4 at 415 Hz
+12
+0
+127
+60
+69
+415.0
+7
+0
+x
+1
+x
+2
+3
+x
+4
+x
+5
+x
+6
+";
+
+#[test]
+fn scale_reads_cents_and_ratios() {
+    let scale = Scale::<128>::parse(MEANTONE).unwrap();
+    assert_eq!(scale.len(), 12);
+    assert!((scale.cents(4) - 386.3137).abs() < 1e-3);
+    assert!((scale.cents(12) - 1200.0).abs() < 1e-9);
+    assert!((scale.cents(-8) - (386.3137 - 1200.0)).abs() < 1e-3);
+}
+
+#[test]
+fn linear_map_tunes_reference() {
+    let tuning = ScalaTuning::<128, 128>::new(
+        Scale::parse(MEANTONE).unwrap(),
+        KeyboardMap::linear(60, 69, 440.0),
+    );
+    assert!((tuning.frequency(69.0) - 440.0).abs() < 1e-3);
+    // Major third above middle C.
+    let ratio = tuning.frequency(64.0) / tuning.frequency(60.0);
+    assert!((ratio - 1.25).abs() < 1e-5);
+    assert!((tuning.frequency(72.0) / tuning.frequency(60.0) - 2.0).abs() < 1e-5);
+}
+
+#[test]
+fn keyboard_map_skips_unmapped_keys() {
+    let diatonic = b"!
+seven white keys
+7
+9/8
+5/4
+4/3
+3/2
+5/3
+15/8
+2/1
+";
+    let tuning = ScalaTuning::<128, 128>::parse(diatonic, WHITE_KEYS).unwrap();
+    assert!((tuning.frequency(69.0) - 415.0).abs() < 1e-3);
+    assert_eq!(tuning.key_frequency(61), None);
+    let fifth = tuning.key_frequency(67).unwrap() / tuning.key_frequency(60).unwrap();
+    assert!((fifth - 1.5).abs() < 1e-6);
+    let octave = tuning.key_frequency(72).unwrap() / tuning.key_frequency(60).unwrap();
+    assert!((octave - 2.0).abs() < 1e-6);
+
+    let black_reference = b"12\n0\n127\n60\n70\n440\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n";
+    let error = KeyboardMap::<128>::parse(black_reference).unwrap_err();
+    assert_eq!(
+        (error.line, error.kind),
+        (5, ScalaErrorKind::UnmappedReference)
+    );
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    let cases: [(&[u8], usize, ScalaErrorKind); 5] = [
+        (b"desc\n2\n100.0\n", 4, ScalaErrorKind::UnexpectedEnd),
+        (b"desc\ntwo\n", 2, ScalaErrorKind::InvalidNumber),
+        (
+            b"desc\n2\n! comment\n3/0\n2/1\n",
+            4,
+            ScalaErrorKind::InvalidPitch,
+        ),
+        (b"desc\n0\n", 2, ScalaErrorKind::EmptyScale),
+        (b"desc\n1\n\xff\n", 3, ScalaErrorKind::InvalidUtf8),
+    ];
+    for (bytes, line, kind) in cases {
+        let error = Scale::<128>::parse(bytes).unwrap_err();
+        assert_eq!((error.line, error.kind), (line, kind));
+    }
+
+    let error = Scale::<4>::parse(MEANTONE).unwrap_err();
+    assert_eq!(error.line, 4);
+    assert_eq!(error.kind, ScalaErrorKind::TooManyNotes { capacity: 4 });
+
+    let error = KeyboardMap::<128>::parse(b"0\n0\n128\n").unwrap_err();
+    assert_eq!((error.line, error.kind), (3, ScalaErrorKind::KeyOutOfRange));
+    let error = KeyboardMap::<128>::parse(b"1\n0\n127\n60\n69\n440\n12\ny\n").unwrap_err();
+    assert_eq!((error.line, error.kind), (8, ScalaErrorKind::InvalidDegree));
+}
@@ -0,0 +1,163 @@
+use osc_tuner::{
+    cache::NoCache,
+    config::TuneConfig,
+    error::TuneError,
+    parts::Composed,
+    settling::{self, SettleDelay},
+    sim::{block_on, SimConfig, SimulatedOscf},
+    table::Table,
+    OscfExt,
+};
+use std::cell::RefCell;
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+fn gliding() -> SimulatedOscf<u12> {
+    SimulatedOscf::new(SimConfig {
+        settle_time: 2e-4,
+        ..realistic()
+    })
+}
+
+fn tune_settled(
+    settling: settling::Settling,
+) -> (
+    SimulatedOscf<u12>,
+    Result<Tuned, TuneError<core::convert::Infallible>>,
+) {
+    let mut osc = gliding();
+    let tuned = tune_with(&mut osc, &TuneConfig::default().with_settling(settling));
+    (osc, tuned)
+}
+
+#[test]
+fn unsettled_readings_break_the_search() {
+    let (_, tuned) = tune_settled(settling::Settling::default());
+    assert!(tuned.is_err());
+}
+
+#[test]
+fn settling_tunes_gliding_oscillator() {
+    let delays = [
+        SettleDelay::None,
+        SettleDelay::Adaptive {
+            tolerance: 1e-4,
+            max_readings: 64,
+        },
+    ];
+    let mut readings = Vec::new();
+    for (discard, delay) in [16, 0].into_iter().zip(delays) {
+        let (osc, tuned) = tune_settled(settling::Settling { discard, delay });
+        assert_anchors(&osc, &tuned.unwrap(), 0.1);
+        readings.push(osc.measurements());
+    }
+    // Only waits as long as each write needs.
+    assert!(readings[1] < readings[0]);
+}
+
+#[test]
+fn adaptive_delay_reports_an_oscillator_that_keeps_gliding() {
+    let (_, tuned) = tune_settled(settling::Settling {
+        discard: 0,
+        delay: SettleDelay::Adaptive {
+            tolerance: 1e-4,
+            max_readings: 2,
+        },
+    });
+    assert_eq!(tuned.err(), Some(TuneError::Unsettled));
+}
+
+#[test]
+fn discarded_readings_follow_each_write() {
+    let mut plain = SimulatedOscf::<u12>::new(realistic());
+    tune(&mut plain).unwrap();
+
+    let mut discarding = SimulatedOscf::<u12>::new(realistic());
+    let settling = settling::Settling {
+        discard: 2,
+        delay: SettleDelay::None,
+    };
+    tune_with(
+        &mut discarding,
+        &TuneConfig::default().with_settling(settling),
+    )
+    .unwrap();
+    // Main and offset writes before one reading settle once.
+    assert_eq!(discarding.measurements(), 3 * plain.measurements());
+}
+
+#[test]
+fn fixed_settle_delay_takes_no_readings() {
+    let board = RefCell::new(gliding());
+    let mut osc = Composed::new(
+        Counter(&board),
+        Channel(&board, false),
+        Some(Channel(&board, true)),
+    )
+    .with_settle(|| {
+        board
+            .borrow_mut()
+            .wait(core::time::Duration::from_millis(2));
+        core::future::ready(())
+    });
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio = block_on(osc.tune_midi_frequencies(
+        &mut main,
+        &mut offset,
+        &NoCache::new(),
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    assert_anchors(&board.borrow(), &tuned, 0.1);
+    // As many readings as an oscillator that never glides.
+    let mut alone = SimulatedOscf::new(realistic());
+    tune(&mut alone).unwrap();
+    assert_eq!(board.borrow().measurements(), alone.measurements());
+}
+
+#[test]
+fn fixed_delay_in_config_uses_the_oscillator_delay() {
+    let settling = settling::Settling {
+        discard: 0,
+        delay: SettleDelay::Fixed(core::time::Duration::from_millis(2)),
+    };
+    let config = TuneConfig::default().with_settling(settling);
+    let mut alone = SimulatedOscf::<u12>::new(realistic());
+    tune(&mut alone).unwrap();
+
+    // The simulator waits without reading.
+    let mut osc = gliding();
+    let tuned = tune_with(&mut osc, &config).unwrap();
+    assert_anchors(&osc, &tuned, 0.1);
+    assert_eq!(osc.measurements(), alone.measurements());
+
+    // Without a delay of its own, an oscillator reads the time away.
+    let board = RefCell::new(gliding());
+    let mut composed = Composed::new(
+        Counter(&board),
+        Channel(&board, false),
+        Some(Channel(&board, true)),
+    );
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio =
+        block_on(composed.tune_midi_frequencies(&mut main, &mut offset, &NoCache::new(), &config))
+            .unwrap();
+    let tuned = Tuned {
+        main,
+        offset,
+        ratio,
+    };
+    assert_anchors(&board.borrow(), &tuned, 0.1);
+    assert!(board.borrow().measurements() > alone.measurements());
+}
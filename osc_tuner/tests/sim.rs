@@ -0,0 +1,333 @@
+use osc_tuner::{
+    cache::{Cache, DirectMappedCache, FixedCache, Generation, LruCache, NoCache, SearchPathCache},
+    config::TuneConfig,
+    domain::MidiOscDomain,
+    error::{Limit, TuneError},
+    period::{MicrosPeriod, Ticks},
+    search::SearchStrategy,
+    sim::{block_on, SimConfig, SimulatedOscf},
+    table::{NoteTable, Table},
+    OscfExt,
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+#[test]
+fn ideal_oscillator_tunes_every_anchor() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+    let tuned = tune(&mut osc).unwrap();
+    assert!((31..=33).contains(&u16::from(tuned.ratio)));
+    assert_anchors(&osc, &tuned, 0.5);
+}
+
+#[test]
+fn converter_errors_are_tuned_out() {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let tuned = tune(&mut osc).unwrap();
+    assert_anchors(&osc, &tuned, 0.5);
+}
+
+#[test]
+fn jitter_stays_within_bound() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        jitter: 2e-4,
+        ..realistic()
+    })
+    .with_seed(7);
+    let tuned = tune(&mut osc).unwrap();
+    assert_anchors(&osc, &tuned, 2.0);
+}
+
+#[test]
+fn interpolated_notes_follow_the_curve() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+    let tuned = tune(&mut osc).unwrap();
+    for note in [13.5, 30.0, 42.25, 55.0, 61.5, 99.9] {
+        let error = cents_error(&osc, &tuned, note);
+        assert!(
+            error.abs() <= bound(note, 2.0),
+            "note {note}: {error:.2} cents"
+        );
+    }
+}
+
+#[test]
+fn bent_fractional_notes_hit_their_pitch() {
+    // An ideal converter, so a main code fraction lost in the split to the
+    // offset DAC shows up as up to one main step.
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+    let tuned = tune(&mut osc).unwrap();
+    let engine = osc_tuner::pitch::PitchEngine::new(&tuned.main, &tuned.offset, tuned.ratio);
+    for note in [61.5, 66.37, 72.25, 88.81, 101.5, 113.93] {
+        for cents in [-150.0, -37.5, 12.0, 50.0, 200.0] {
+            let (main, offset) = engine.dac_pair(MidiOscDomain::new(note).unwrap(), cents);
+            let bent = note + cents / 100.0;
+            let error = 1200.0 * (osc.frequency(main, offset) / target_frequency(bent)).log2();
+            assert!(
+                error.abs() <= bound(bent, 0.5),
+                "note {note} {cents:+} cents: {error:.2} cents"
+            );
+        }
+    }
+}
+
+#[test]
+fn fine_counter_tunes_top_octave_tightly() {
+    let mut osc = SimulatedOscf::<u12, Ticks<72_000_000>>::new(realistic());
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let ratio = block_on(osc.tune_midi_frequencies(
+        &mut main,
+        &mut offset,
+        &NoCache::<u12, Ticks<72_000_000>>::new(),
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    let engine = osc_tuner::pitch::PitchEngine::new(&main, &offset, ratio);
+    for note in 108..=120 {
+        let note = note as f32;
+        let (main, offset) = engine.dac_pair(MidiOscDomain::new(note).unwrap(), 0.0);
+        let error = 1200.0 * (osc.frequency(main, offset) / target_frequency(note)).log2();
+        // A microsecond counter is off by up to 14 cents at the top.
+        assert!(error.abs() <= 0.5, "note {note}: {error:.2} cents");
+    }
+}
+
+#[test]
+fn note_table_follows_poor_mid_range_tracking() {
+    let config = SimConfig {
+        inl: 12.0,
+        ..realistic()
+    };
+    let mut osc = SimulatedOscf::<u12>::new(config);
+    let compact = tune(&mut osc).unwrap();
+
+    let mut main = NoteTable::<u12, 12, 3, 37>::new();
+    let mut offset = NoteTable::new();
+    let ratio = block_on(osc.tune_midi_frequencies(
+        &mut main,
+        &mut offset,
+        &NoCache::new(),
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+    let engine = osc_tuner::pitch::PitchEngine::new(&main, &offset, ratio);
+
+    let mut worst_compact: f64 = 0.0;
+    for note in 12..=120 {
+        let note = note as f32;
+        let (main, offset) = engine.dac_pair(MidiOscDomain::new(note).unwrap(), 0.0);
+        let error = 1200.0 * (osc.frequency(main, offset) / target_frequency(note)).log2();
+        assert!(
+            error.abs() <= bound(note, 2.0),
+            "note {note}: {error:.2} cents"
+        );
+        worst_compact = worst_compact.max(cents_error(&osc, &compact, note).abs());
+    }
+    assert!(worst_compact > 3.0, "{worst_compact:.2} cents");
+}
+
+#[test]
+fn cache_saves_measurements() {
+    let mut uncached = SimulatedOscf::<u12>::new(SimConfig::default());
+    tune(&mut uncached).unwrap();
+
+    let mut cached = SimulatedOscf::<u12>::new(SimConfig::default());
+    let cache = FixedCache::<u12, 4096>::new();
+    block_on(cached.tune_midi_frequencies(
+        &mut Table::new(),
+        &mut Table::new(),
+        &cache,
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    assert!(cached.measurements() < uncached.measurements());
+}
+
+#[test]
+fn bounded_caches_save_measurements() {
+    let mut uncached = SimulatedOscf::<u12>::new(SimConfig::default());
+    tune(&mut uncached).unwrap();
+
+    fn tune_cached(cache: &impl Cache<Index = u12, Period = MicrosPeriod>) -> usize {
+        let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+        let mut main = Table::new();
+        let mut offset = Table::new();
+        let ratio = block_on(osc.tune_midi_frequencies(
+            &mut main,
+            &mut offset,
+            cache,
+            &TuneConfig::default(),
+        ))
+        .unwrap();
+        assert_anchors(
+            &osc,
+            &Tuned {
+                main,
+                offset,
+                ratio,
+            },
+            0.5,
+        );
+        osc.measurements()
+    }
+
+    let lru = LruCache::<u12, 32>::new();
+    let direct = DirectMappedCache::<u12, 64>::new();
+    let path = SearchPathCache::<u12, 14, 2>::new();
+    for (measurements, stats) in [
+        (tune_cached(&lru), lru.stats()),
+        (tune_cached(&direct), direct.stats()),
+        (tune_cached(&path), path.stats()),
+    ] {
+        assert!(measurements < uncached.measurements());
+        assert!(stats.hits > 0, "{stats:?}");
+    }
+}
+
+#[test]
+fn invalidated_cache_follows_drift() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        tempco: 2.0,
+        ..realistic()
+    });
+    let cache = FixedCache::<u12, 4096, MicrosPeriod, Generation>::new();
+    let tune_cached = |osc: &mut SimulatedOscf<u12>| {
+        let mut main = Table::new();
+        let mut offset = Table::new();
+        let ratio = block_on(osc.tune_midi_frequencies(
+            &mut main,
+            &mut offset,
+            &cache,
+            &TuneConfig::default(),
+        ))
+        .unwrap();
+        Tuned {
+            main,
+            offset,
+            ratio,
+        }
+    };
+    tune_cached(&mut osc);
+
+    osc.temperature += 5.0;
+    cache.expiry().invalidate();
+    let tuned = tune_cached(&mut osc);
+    assert_anchors(&osc, &tuned, 0.5);
+}
+
+#[test]
+fn dead_oscillator_is_reported() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        dead: true,
+        ..SimConfig::default()
+    });
+    assert_eq!(tune(&mut osc).err(), Some(TuneError::Dead));
+}
+
+#[test]
+fn narrow_range_is_unreachable() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        base_frequency: 100.0,
+        ..SimConfig::default()
+    });
+    assert!(matches!(
+        tune(&mut osc),
+        Err(TuneError::Unreachable {
+            limit: Limit::Min,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn slow_oscillator_times_out() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        base_frequency: 1.0,
+        ..SimConfig::default()
+    });
+    assert_eq!(tune(&mut osc).err(), Some(TuneError::Timeout));
+}
+
+#[test]
+fn retune_follows_drift_with_few_measurements() {
+    let mut osc = SimulatedOscf::<u12>::new(SimConfig {
+        tempco: 2.0,
+        ..realistic()
+    });
+    let mut tuned = tune(&mut osc).unwrap();
+    let full = osc.measurements();
+
+    osc.temperature += 3.0;
+    let before = osc.measurements();
+    tuned.ratio = block_on(osc.retune_midi_frequencies(
+        &mut tuned.main,
+        &mut tuned.offset,
+        tuned.ratio,
+        &TuneConfig::default(),
+    ))
+    .unwrap();
+
+    assert_anchors(&osc, &tuned, 0.5);
+    let retune = osc.measurements() - before;
+    assert!(retune * 3 < full, "{retune} of {full}");
+}
+
+#[test]
+fn retune_reports_unreachable_on_16_bit_dac() {
+    let mut osc = SimulatedOscf::<u16>::new(SimConfig {
+        codes_per_octave: 3000.0,
+        ..realistic()
+    });
+    let mut main = Table::new();
+    let mut offset = Table::new();
+    let config = TuneConfig {
+        timeout: core::time::Duration::from_secs(1000),
+        ..TuneConfig::default()
+    };
+    let ratio =
+        block_on(osc.tune_midi_frequencies(&mut main, &mut offset, &NoCache::new(), &config))
+            .unwrap();
+
+    // Seeds stay low while the top anchors move past the end of the range.
+    osc.config.offset_error -= 13.0;
+    let retuned = block_on(osc.retune_midi_frequencies(&mut main, &mut offset, ratio, &config));
+    assert!(
+        matches!(
+            retuned,
+            Err(TuneError::Unreachable {
+                limit: Limit::Max,
+                ..
+            })
+        ),
+        "{retuned:?}"
+    );
+}
+
+#[test]
+fn interpolation_matches_bisection_with_fewer_readings() {
+    let curved = SimConfig {
+        inl: 40.0,
+        reset_time: 5e-6,
+        ..realistic()
+    };
+    for sim in [SimConfig::default(), realistic(), curved] {
+        let mut bisected = SimulatedOscf::<u12>::new(sim);
+        let expected = tune(&mut bisected).unwrap();
+
+        let mut interpolated = SimulatedOscf::<u12>::new(sim);
+        let tuned = tune_with(
+            &mut interpolated,
+            &TuneConfig::default().with_search(SearchStrategy::Interpolation),
+        )
+        .unwrap();
+
+        assert_same_tables(&tuned, &expected);
+        assert!(interpolated.measurements() * 2 < bisected.measurements());
+    }
+}
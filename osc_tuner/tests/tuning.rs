@@ -0,0 +1,103 @@
+use osc_tuner::{
+    config::TuneConfig,
+    domain::ReferencePitch,
+    error::TuneError,
+    sim::{SimConfig, SimulatedOscf},
+    tuning::{Edo, JustIntonation, TuningSystem},
+};
+use std::num::NonZeroU16;
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+// Anchors and the notes between them, which the tables only reach through
+// the tuning. Between anchors the converter's INL goes uncorrected.
+fn assert_tuning(tuning: impl TuningSystem + Copy) {
+    let mut osc = SimulatedOscf::<u12>::new(realistic());
+    let tuned = tune_with(&mut osc, &TuneConfig::default().with_tuning(tuning)).unwrap();
+    for note in (12..=120).map(|n| n as f32).chain([30.5, 66.5]) {
+        let error = cents_error_in(&osc, &tuned, note, tuning);
+        let micros = 1e6 / tuning.frequency(note) as f64;
+        let headroom = if anchors().any(|a| a == note) {
+            0.5
+        } else {
+            4.0
+        };
+        let bound = 1200.0 * (1.0 + 1.0 / micros).log2() + headroom;
+        assert!(error.abs() <= bound, "note {note}: {error:.2} cents");
+    }
+}
+
+#[test]
+fn baroque_pitch() {
+    let tuning = Edo::new(
+        ReferencePitch::new(415.0).unwrap(),
+        NonZeroU16::new(12).unwrap(),
+    );
+    assert!((tuning.frequency(69.0) - 415.0).abs() < 1e-3);
+    assert_tuning(tuning);
+}
+
+#[test]
+fn nineteen_edo() {
+    let tuning = Edo::new(
+        ReferencePitch::new(440.0).unwrap(),
+        NonZeroU16::new(19).unwrap(),
+    );
+    assert!((tuning.frequency(88.0) - 880.0).abs() < 1e-2);
+    assert_tuning(tuning);
+}
+
+#[test]
+fn just_intonation_from_d() {
+    let tuning = JustIntonation::five_limit(ReferencePitch::new(440.0).unwrap(), 62);
+    let d4 = target_frequency(62.0) as f32;
+    assert!((tuning.frequency(69.0) / d4 - 1.5).abs() < 1e-5);
+    assert!((tuning.frequency(54.0) / d4 - 0.625).abs() < 1e-5);
+    assert_tuning(tuning);
+}
+
+#[test]
+fn scala_scale() {
+    let scl = b"! meanquar.scl
+1/4-comma meantone scale
+12
+76.049
+193.157
+310.265
+5/4
+503.422
+579.471
+696.578
+25/16
+889.735
+1006.843
+1082.892
+2/1
+";
+    let map = osc_tuner::scala::KeyboardMap::linear(60, 69, 440.0);
+    let scale = osc_tuner::scala::Scale::parse(scl).unwrap();
+    assert_tuning(osc_tuner::scala::ScalaTuning::<128, 128>::new(scale, map));
+}
+
+#[test]
+fn scala_reference_out_of_range_is_an_error() {
+    let scale = osc_tuner::scala::Scale::parse(b"12-TET\n1\n2/1\n").unwrap();
+    for frequency in [1e-9, 1e9] {
+        let map = osc_tuner::scala::KeyboardMap::linear(60, 69, frequency);
+        let tuning = osc_tuner::scala::ScalaTuning::<128, 128>::new(scale, map);
+        let mut osc = SimulatedOscf::<u12>::new(SimConfig::default());
+        assert!(matches!(
+            tune_with(&mut osc, &TuneConfig::default().with_tuning(tuning)),
+            Err(TuneError::OutOfRange { note: 12 })
+        ));
+    }
+}
+
+#[test]
+#[should_panic]
+fn just_intonation_rejects_zero_denominator() {
+    JustIntonation::new(ReferencePitch::new(440.0).unwrap(), 60, [(1, 1), (3, 0)]);
+}
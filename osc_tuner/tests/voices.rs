@@ -0,0 +1,134 @@
+use osc_tuner::{
+    config::TuneConfig,
+    sim::{block_on, SimConfig, SimulatedOscf},
+    table::Table,
+    voices::{tune_voices, SharedCounter},
+    TryOscf,
+};
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Wake, Waker},
+};
+
+use uxt::u12;
+
+mod common;
+use common::*;
+
+// Yields once after every DAC write and while reading, and logs which voice
+// holds the counter.
+struct Settling<'a> {
+    osc: SimulatedOscf<u12>,
+    id: usize,
+    log: &'a core::cell::RefCell<Vec<usize>>,
+    reading: &'a core::cell::Cell<bool>,
+}
+
+async fn yield_once() {
+    let mut yielded = false;
+    core::future::poll_fn(|cx| {
+        if core::mem::replace(&mut yielded, true) {
+            core::task::Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }
+    })
+    .await
+}
+
+impl TryOscf for Settling<'_> {
+    type DacValue = u12;
+    type Error = core::convert::Infallible;
+
+    async fn try_get_period(
+        &mut self,
+        timeout: core::time::Duration,
+    ) -> Result<osc_tuner::period::MicrosPeriod, osc_tuner::error::MeasureError<Self::Error>> {
+        assert!(!self.reading.replace(true), "counter read by two voices");
+        self.log.borrow_mut().push(self.id);
+        yield_once().await;
+        let period = self.osc.try_get_period(timeout).await;
+        self.reading.set(false);
+        period
+    }
+
+    async fn try_set_main_dac(&mut self, value: u12) -> Result<(), Self::Error> {
+        self.osc.try_set_main_dac(value).await?;
+        yield_once().await;
+        Ok(())
+    }
+
+    async fn try_set_offset_dac(&mut self, value: u12) -> Result<(), Self::Error> {
+        self.osc.try_set_offset_dac(value).await?;
+        yield_once().await;
+        Ok(())
+    }
+}
+
+#[test]
+fn voices_are_interleaved_on_one_counter() {
+    let configs: [SimConfig; 4] = core::array::from_fn(|i| SimConfig {
+        scale_error: 0.01 * i as f64 - 0.015,
+        offset_error: 0.05 * i as f64,
+        ..realistic()
+    });
+    let log = core::cell::RefCell::new(Vec::new());
+    let reading = core::cell::Cell::new(false);
+    let mut voices: [_; 4] = core::array::from_fn(|id| Settling {
+        osc: SimulatedOscf::new(configs[id]),
+        id,
+        log: &log,
+        reading: &reading,
+    });
+
+    let counter = SharedCounter::new();
+    let results = block_on(tune_voices::<_, Table<u12>, _, 4>(
+        &mut voices,
+        &counter,
+        &TuneConfig::default(),
+    ));
+
+    for (voice, result) in voices.iter_mut().zip(results) {
+        let tuned = result.unwrap();
+        let alone = tune(&mut SimulatedOscf::new(voice.osc.config)).unwrap();
+        assert_same_tables(&tuned, &alone);
+    }
+
+    let log = log.into_inner();
+    let switches = log.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    assert!(switches > log.len() / 2, "{switches} of {}", log.len());
+}
+
+#[test]
+fn waiting_voice_sleeps_until_the_counter_is_free() {
+    struct Wakes(AtomicUsize);
+
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let wakes = Arc::new(Wakes(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let counter = SharedCounter::new();
+    let guard = block_on(counter.lock());
+    let mut waiting = pin!(counter.lock());
+    for _ in 0..3 {
+        assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    }
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 0);
+
+    drop(guard);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert!(waiting.as_mut().poll(&mut cx).is_ready());
+}